@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone)]
+pub enum ApprovalPolicyError {
+    #[error("Error reading approval policy {0}: {1}")]
+    Read(String, String),
+    #[error("Invalid approval policy {0}: {1}")]
+    Invalid(String, String),
+    #[error("Rule {0} is defined more than once in the approval policy")]
+    DuplicatedRule(String),
+}
@@ -0,0 +1,185 @@
+mod error;
+mod rules;
+
+use std::{str::FromStr, sync::Arc};
+
+pub use error::ApprovalPolicyError;
+pub use rules::{ApprovalContext, Rule, RuleSet, Vote};
+use serde::{Deserialize, Serialize};
+use taple_core::{
+    identifier::{Derivable, DigestIdentifier},
+    request::EventRequest,
+    Api, ApprovalEntity, ApprovalState, DatabaseCollection, Notification,
+};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+
+use crate::{database::leveldb::LDBCollection, notifications::NotificationHub, utils::unix_time};
+
+const DECISIONS_PREFIX: &str = "approvals/decisions/";
+
+/// Vote cast by the approval policy, kept for audit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Decision {
+    pub approval_id: String,
+    pub subject_id: String,
+    pub sn: u64,
+    /// Name of the rule that matched the request
+    pub rule: String,
+    pub vote: Vote,
+    /// Seconds since the Unix epoch
+    pub decided_at: u64,
+    /// Error returned by the node when casting the vote
+    pub error: Option<String>,
+}
+
+/// Votes the requests for approval received by the node according to the rules of the
+/// approval policy. Requests that no rule matches are left for a manual vote
+#[derive(Clone)]
+pub struct ApprovalPolicy {
+    node: Api,
+    db: Arc<LDBCollection>,
+    rules: Arc<RuleSet>,
+}
+
+impl ApprovalPolicy {
+    pub fn new(node: Api, db: Arc<LDBCollection>, rules: RuleSet) -> Self {
+        Self {
+            node,
+            db,
+            rules: Arc::new(rules),
+        }
+    }
+
+    /// Vote cast by the policy on a request for approval
+    pub fn decision(&self, approval_id: &str) -> Option<Decision> {
+        self.db
+            .get(&format!("{}{}", DECISIONS_PREFIX, approval_id))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+    }
+
+    /// Reviews the pending requests and then every new one until the token is cancelled
+    pub fn spawn(&self, notifications: &NotificationHub, cancellation_token: CancellationToken) {
+        if self.rules.is_empty() {
+            return;
+        }
+        let policy = self.clone();
+        // Subscribed before reviewing the pending requests so no request is missed
+        let mut receiver = notifications.subscribe();
+        tokio::spawn(async move {
+            policy.review_pending().await;
+            loop {
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => break,
+                    notification = receiver.recv() => match notification {
+                        Ok(Notification::ApprovalReceived { id, .. }) => policy.review(&id).await,
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(_)) => policy.review_pending().await,
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            }
+        });
+    }
+
+    async fn review_pending(&self) {
+        match self
+            .node
+            .get_approvals(Some(ApprovalState::Pending), None, None)
+            .await
+        {
+            Ok(approvals) => {
+                for approval in approvals {
+                    self.review_approval(approval).await;
+                }
+            }
+            Err(error) => log::error!("Error getting the pending requests for approval: {}", error),
+        }
+    }
+
+    async fn review(&self, id: &str) {
+        let Ok(id) = DigestIdentifier::from_str(id) else {
+            return;
+        };
+        match self.node.get_approval(id).await {
+            Ok(approval) => self.review_approval(approval).await,
+            Err(error) => log::error!("Error getting the request for approval: {}", error),
+        }
+    }
+
+    async fn review_approval(&self, approval: ApprovalEntity) {
+        let approval_id = approval.id.to_str();
+        // Requests whose vote failed are reviewed again
+        let voted = self
+            .decision(&approval_id)
+            .map_or(false, |decision| decision.error.is_none());
+        if voted || !matches!(approval.state, ApprovalState::Pending) {
+            return;
+        }
+        let request = &approval.request.content;
+        let EventRequest::Fact(fact) = &request.event_request.content else {
+            return;
+        };
+        let subject = match self.node.get_subject(fact.subject_id.clone()).await {
+            Ok(subject) => subject,
+            Err(error) => {
+                log::error!(
+                    "Error getting the subject of request for approval {}: {}",
+                    approval_id,
+                    error
+                );
+                return;
+            }
+        };
+        let subject_id = subject.subject_id.to_str();
+        let context = ApprovalContext {
+            governance_id: &subject.governance_id.to_str(),
+            schema_id: &subject.schema_id,
+            subject_id: &subject_id,
+            signer: &request.event_request.signature.signer.to_str(),
+            patch: &request.patch.0,
+        };
+        let Some(rule) = self.rules.first_match(&context) else {
+            log::debug!("No rule matches request for approval {}", approval_id);
+            return;
+        };
+        let result = self
+            .node
+            .approval_request(approval.id.clone(), rule.vote == Vote::Accept)
+            .await;
+        match &result {
+            Ok(_) => log::info!(
+                "Request for approval {} voted {:?} by rule {}",
+                approval_id,
+                rule.vote,
+                rule.name
+            ),
+            Err(error) => log::error!(
+                "Error voting request for approval {}: {}",
+                approval_id,
+                error
+            ),
+        }
+        let decision = Decision {
+            approval_id,
+            subject_id,
+            sn: request.sn,
+            rule: rule.name.clone(),
+            vote: rule.vote,
+            decided_at: unix_time().as_secs(),
+            error: result.err().map(|error| error.to_string()),
+        };
+        let data = serde_json::to_vec(&decision).expect("Serialize approval decision");
+        if let Err(error) = self.db.put(
+            &format!("{}{}", DECISIONS_PREFIX, decision.approval_id),
+            data,
+        ) {
+            log::error!(
+                "Error recording the vote of request for approval {}: {}",
+                decision.approval_id,
+                error
+            );
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::error::ApprovalPolicyError;
+
+/// Vote cast by a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Vote {
+    Accept,
+    Reject,
+}
+
+/// Rule of the approval policy. Every condition given must hold for the rule to match
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Name recorded with the votes cast by the rule
+    pub name: String,
+    pub vote: Vote,
+    pub governance_id: Option<String>,
+    pub schema_id: Option<String>,
+    pub subject_id: Option<String>,
+    /// Issuers of the event request
+    #[serde(default)]
+    pub signers: Vec<String>,
+    /// Every operation of the patch must change one of these paths or a value below them.
+    /// A "*" segment matches any segment
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Values that the patch must set, by path
+    #[serde(default)]
+    pub values: BTreeMap<String, Value>,
+}
+
+/// Data of a request for approval checked by the rules
+pub struct ApprovalContext<'a> {
+    pub governance_id: &'a str,
+    pub schema_id: &'a str,
+    pub subject_id: &'a str,
+    pub signer: &'a str,
+    pub patch: &'a Value,
+}
+
+impl Rule {
+    pub fn matches(&self, context: &ApprovalContext) -> bool {
+        let equals = |condition: &Option<String>, value: &str| {
+            condition
+                .as_ref()
+                .map_or(true, |condition| condition == value)
+        };
+        equals(&self.governance_id, context.governance_id)
+            && equals(&self.schema_id, context.schema_id)
+            && equals(&self.subject_id, context.subject_id)
+            && (self.signers.is_empty() || self.signers.iter().any(|s| s == context.signer))
+            && self.patch_matches(context.patch)
+    }
+
+    fn patch_matches(&self, patch: &Value) -> bool {
+        if self.paths.is_empty() && self.values.is_empty() {
+            return true;
+        }
+        let Some(operations) = patch.as_array() else {
+            return false;
+        };
+        let allowed = |path: Option<&str>| {
+            path.map_or(false, |path| {
+                self.paths.iter().any(|pattern| path_matches(pattern, path))
+            })
+        };
+        // Move and copy also read another path, and move removes it
+        let paths_match = self.paths.is_empty()
+            || operations.iter().all(|operation| {
+                allowed(operation["path"].as_str())
+                    && (!matches!(operation["op"].as_str(), Some("move" | "copy"))
+                        || allowed(operation["from"].as_str()))
+            });
+        // The value must be the one left by the last operation that touches the path
+        let values_match = self.values.iter().all(|(path, value)| {
+            let last = operations
+                .iter()
+                .rev()
+                .find(|operation| touches(operation, path));
+            last.map_or(false, |operation| {
+                matches!(operation["op"].as_str(), Some("add" | "replace"))
+                    && operation["path"].as_str() == Some(path.as_str())
+                    && operation["value"] == *value
+            })
+        });
+        paths_match && values_match
+    }
+}
+
+/// Whether an operation of a patch changes the value of a path, that is, it modifies
+/// the path, one above it or one below it
+fn touches(operation: &Value, path: &str) -> bool {
+    if operation["op"].as_str() == Some("test") {
+        return false;
+    }
+    let related = |other: Option<&str>| {
+        other.map_or(false, |other| {
+            path_matches(path, other) || path_matches(other, path)
+        })
+    };
+    related(operation["path"].as_str())
+        || (operation["op"].as_str() == Some("move") && related(operation["from"].as_str()))
+}
+
+/// Whether a JSON Pointer is the one of the pattern or is below it
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let mut pattern = pattern.trim_end_matches('/').split('/');
+    let mut path = path.split('/');
+    loop {
+        match (pattern.next(), path.next()) {
+            (None, _) => return true,
+            (Some(expected), Some(segment)) if expected == "*" || expected == segment => {}
+            _ => return false,
+        }
+    }
+}
+
+/// Rules of the approval policy, checked in order. The first one that matches casts the vote
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    #[serde(default, rename = "rule")]
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// Reads the rules from a TOML file with a `[[rule]]` table for each one
+    pub fn load(path: &str) -> Result<Self, ApprovalPolicyError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|error| ApprovalPolicyError::Read(path.to_owned(), error.to_string()))?;
+        let rules: RuleSet = toml::from_str(&content)
+            .map_err(|error| ApprovalPolicyError::Invalid(path.to_owned(), error.to_string()))?;
+        let mut names = HashSet::new();
+        if let Some(rule) = rules.rules.iter().find(|rule| !names.insert(&rule.name)) {
+            return Err(ApprovalPolicyError::DuplicatedRule(rule.name.clone()));
+        }
+        Ok(rules)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn first_match(&self, context: &ApprovalContext) -> Option<&Rule> {
+        self.rules.iter().find(|rule| rule.matches(context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn rule(paths: &[&str], values: Value) -> Rule {
+        Rule {
+            name: "test".to_owned(),
+            vote: Vote::Accept,
+            governance_id: None,
+            schema_id: None,
+            subject_id: None,
+            signers: Vec::new(),
+            paths: paths.iter().map(|path| path.to_string()).collect(),
+            values: serde_json::from_value(values).unwrap(),
+        }
+    }
+
+    #[test]
+    fn path_matches_the_path_and_below() {
+        assert!(path_matches("/comments", "/comments"));
+        assert!(path_matches("/comments", "/comments/0"));
+        assert!(path_matches("/comments/", "/comments/0/text"));
+        assert!(path_matches("/items/*/count", "/items/3/count"));
+        assert!(!path_matches("/comments", "/owner"));
+        assert!(!path_matches("/comments", "/comment"));
+        assert!(!path_matches("/comments/0", "/comments"));
+        assert!(!path_matches("/items/*/count", "/items/3/name"));
+    }
+
+    #[test]
+    fn patch_matches_paths() {
+        let rule = rule(&["/comments"], json!({}));
+        assert!(rule.patch_matches(&json!([
+            { "op": "add", "path": "/comments/0", "value": "ok" }
+        ])));
+        assert!(!rule.patch_matches(&json!([
+            { "op": "add", "path": "/comments/0", "value": "ok" },
+            { "op": "remove", "path": "/owner" }
+        ])));
+        assert!(!rule.patch_matches(&json!({ "op": "remove", "path": "/comments" })));
+    }
+
+    #[test]
+    fn patch_matches_checks_from_of_move_and_copy() {
+        let rule = rule(&["/comments"], json!({}));
+        assert!(!rule.patch_matches(&json!([
+            { "op": "move", "from": "/owner", "path": "/comments/x" }
+        ])));
+        assert!(!rule.patch_matches(&json!([
+            { "op": "copy", "from": "/secret", "path": "/comments/x" }
+        ])));
+        assert!(!rule.patch_matches(&json!([
+            { "op": "move", "path": "/comments/x" }
+        ])));
+        assert!(rule.patch_matches(&json!([
+            { "op": "move", "from": "/comments/0", "path": "/comments/1" }
+        ])));
+    }
+
+    #[test]
+    fn patch_matches_values_left_by_the_last_operation() {
+        let rule = rule(&[], json!({ "/status": "approved" }));
+        assert!(rule.patch_matches(&json!([
+            { "op": "replace", "path": "/status", "value": "approved" }
+        ])));
+        assert!(!rule.patch_matches(&json!([
+            { "op": "replace", "path": "/status", "value": "rejected" }
+        ])));
+        assert!(!rule.patch_matches(&json!([
+            { "op": "replace", "path": "/status", "value": "approved" },
+            { "op": "replace", "path": "/status", "value": "rejected" }
+        ])));
+        assert!(!rule.patch_matches(&json!([
+            { "op": "replace", "path": "/status", "value": "approved" },
+            { "op": "remove", "path": "/status" }
+        ])));
+        assert!(!rule.patch_matches(&json!([
+            { "op": "replace", "path": "/status", "value": "approved" },
+            { "op": "move", "from": "/status", "path": "/old" }
+        ])));
+        assert!(!rule.patch_matches(&json!([
+            { "op": "replace", "path": "/status", "value": "approved" },
+            { "op": "replace", "path": "", "value": {} }
+        ])));
+        assert!(rule.patch_matches(&json!([
+            { "op": "replace", "path": "/status", "value": "approved" },
+            { "op": "replace", "path": "/other", "value": 1 }
+        ])));
+        assert!(rule.patch_matches(&json!([
+            { "op": "replace", "path": "/status", "value": "approved" },
+            { "op": "test", "path": "/status", "value": "approved" }
+        ])));
+    }
+}
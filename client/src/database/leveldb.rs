@@ -12,6 +12,8 @@ use taple_core::{
     test_database_manager_trait, DatabaseCollection, DatabaseManager, DbError as Error,
 };
 
+use crate::metrics::{observe_database, ObservedIterator};
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct StringKey(pub String);
 impl db_key::Key for StringKey {
@@ -80,8 +82,17 @@ impl DatabaseManager<LDBCollection> for LevelDBManager {
     }
 
     fn create_collection(&self, _identifier: &str) -> LDBCollection {
+        self.create_prefixed_collection("")
+    }
+}
+
+impl LevelDBManager {
+    /// Collection whose keys are stored under the given prefix, so they can not collide
+    /// with the ones used by the node
+    pub fn create_prefixed_collection(&self, prefix: &str) -> LDBCollection {
         LDBCollection {
             data: self.db.clone(),
+            prefix: prefix.to_owned(),
             read_options: SyncCell(Cell::new(None)),
             write_options: SyncCell(Cell::new(None)),
         }
@@ -90,13 +101,14 @@ impl DatabaseManager<LDBCollection> for LevelDBManager {
 
 pub struct LDBCollection {
     data: Arc<Database<StringKey>>,
+    prefix: String,
     read_options: SyncCell<Option<ReadOptions>>,
     write_options: SyncCell<Option<leveldb::options::WriteOptions>>,
 }
 
 impl LDBCollection {
     fn generate_key(&self, key: &str) -> StringKey {
-        StringKey(key.to_string())
+        StringKey(format!("{}{}", self.prefix, key))
     }
 
     pub fn get_read_options(&self) -> leveldb::options::ReadOptions<StringKey> {
@@ -121,7 +133,7 @@ impl LDBCollection {
 impl DatabaseCollection for LDBCollection {
     fn get(&self, key: &str) -> Result<Vec<u8>, Error> {
         let key = self.generate_key(key);
-        let result = self.data.get(self.get_read_options(), key);
+        let result = observe_database("get", || self.data.get(self.get_read_options(), key));
         match result {
             Err(_) => Err(Error::EntryNotFound),
             Ok(data) => match data {
@@ -133,13 +145,15 @@ impl DatabaseCollection for LDBCollection {
 
     fn put(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
         let key = self.generate_key(key);
-        let _result = self.data.put(self.get_write_options(), key, &data);
+        let _result = observe_database("put", || {
+            self.data.put(self.get_write_options(), key, &data)
+        });
         Ok(())
     }
 
     fn del(&self, key: &str) -> Result<(), Error> {
         let key = self.generate_key(key);
-        let _result = self.data.delete(self.get_write_options(), key);
+        let _result = observe_database("del", || self.data.delete(self.get_write_options(), key));
         Ok(())
     }
 
@@ -147,6 +161,18 @@ impl DatabaseCollection for LDBCollection {
         &'a self,
         reverse: bool,
         prefix: String,
+    ) -> Box<dyn Iterator<Item = (String, Vec<u8>)> + 'a> {
+        Box::new(ObservedIterator::new("iter", || {
+            self.create_iter(reverse, format!("{}{}", self.prefix, prefix))
+        }))
+    }
+}
+
+impl LDBCollection {
+    fn create_iter<'a>(
+        &'a self,
+        reverse: bool,
+        prefix: String,
     ) -> Box<dyn Iterator<Item = (String, Vec<u8>)> + 'a> {
         if reverse {
             let iter = self.data.iter(self.get_read_options()).reverse();
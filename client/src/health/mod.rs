@@ -0,0 +1,145 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use taple_core::{Api, DatabaseCollection};
+use tokio::{net::TcpStream, time::timeout};
+
+use crate::{database::leveldb::LDBCollection, utils::unix_time};
+
+/// Maximum time given to each check before considering it failed
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+const PROBE_KEY: &str = "health/probe";
+
+/// Result of one of the checks performed to know if the node is ready
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub result: Result<(), String>,
+}
+
+impl Check {
+    fn new(name: &'static str, result: Result<(), String>) -> Self {
+        Self { name, result }
+    }
+}
+
+/// Dependencies the node needs to serve requests
+#[derive(Clone)]
+pub struct Readiness {
+    database: Arc<LDBCollection>,
+    node: Api,
+    node_running: Arc<AtomicBool>,
+    listen_addrs: Vec<String>,
+}
+
+impl Readiness {
+    pub fn new(
+        database: Arc<LDBCollection>,
+        node: Api,
+        node_running: Arc<AtomicBool>,
+        listen_addrs: Vec<String>,
+    ) -> Self {
+        Self {
+            database,
+            node,
+            node_running,
+            listen_addrs,
+        }
+    }
+
+    pub async fn check(&self) -> Vec<Check> {
+        let (database, node, network) = futures::join!(
+            self.check_database(),
+            self.check_node(),
+            self.check_listen_addrs()
+        );
+        vec![
+            Check::new("database", database),
+            Check::new("node", node),
+            Check::new("network", network),
+        ]
+    }
+
+    /// Writes, reads back and deletes a probe entry
+    async fn check_database(&self) -> Result<(), String> {
+        let database = self.database.clone();
+        let probe = tokio::task::spawn_blocking(move || {
+            let value = unix_time().as_nanos().to_be_bytes().to_vec();
+            database
+                .put(PROBE_KEY, value.clone())
+                .map_err(|error| error.to_string())?;
+            let read = database
+                .get(PROBE_KEY)
+                .map_err(|error| format!("Database is not readable: {}", error))?;
+            database.del(PROBE_KEY).map_err(|error| error.to_string())?;
+            if read != value {
+                return Err("Database is not writable".to_owned());
+            }
+            Ok(())
+        });
+        match timeout(CHECK_TIMEOUT, probe).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(error)) => Err(error.to_string()),
+            Err(_) => Err("Database did not respond in time".to_owned()),
+        }
+    }
+
+    async fn check_node(&self) -> Result<(), String> {
+        if !self.node_running.load(Ordering::Relaxed) {
+            return Err("Node is not running".to_owned());
+        }
+        match timeout(
+            CHECK_TIMEOUT,
+            self.node.get_governances("".into(), None, Some(1)),
+        )
+        .await
+        {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(error)) => Err(error.to_string()),
+            Err(_) => Err("Node did not respond in time".to_owned()),
+        }
+    }
+
+    /// Connects to every TCP address the node is listening on
+    async fn check_listen_addrs(&self) -> Result<(), String> {
+        for addr in &self.listen_addrs {
+            let Some(socket_addr) = tcp_socket_addr(addr) else {
+                continue;
+            };
+            match timeout(CHECK_TIMEOUT, TcpStream::connect(socket_addr)).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(error)) => return Err(format!("{} is not bound: {}", addr, error)),
+                Err(_) => return Err(format!("{} did not respond in time", addr)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Socket address of a multiaddress such as `/ip4/0.0.0.0/tcp/50000`. Unspecified
+/// addresses are replaced by the loopback one. Other transports are ignored.
+fn tcp_socket_addr(addr: &str) -> Option<SocketAddr> {
+    let mut parts = addr.trim_start_matches('/').split('/');
+    let ip: IpAddr = match (parts.next()?, parts.next()?) {
+        ("ip4", ip) => ip.parse::<Ipv4Addr>().ok()?.into(),
+        ("ip6", ip) => ip.parse::<Ipv6Addr>().ok()?.into(),
+        _ => return None,
+    };
+    let port = match (parts.next()?, parts.next()?) {
+        ("tcp", port) => port.parse().ok()?,
+        _ => return None,
+    };
+    let ip = match ip {
+        IpAddr::V4(ip) if ip.is_unspecified() => Ipv4Addr::LOCALHOST.into(),
+        IpAddr::V6(ip) if ip.is_unspecified() => Ipv6Addr::LOCALHOST.into(),
+        ip => ip,
+    };
+    Some(SocketAddr::new(ip, port))
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use taple_core::{
+    identifier::{Derivable, DigestIdentifier},
+    request::EventRequest,
+    Api, ApiError, ApprovalEntity, ApprovalState, SubjectData,
+};
+
+use super::{bodys::ApprovalFilterBody, governance::GOVERNANCE_SCHEMA, querys::GetApprovalsQuery};
+use crate::utils::Batches;
+
+impl ApprovalFilterBody {
+    pub fn is_empty(&self) -> bool {
+        self.subject_id.is_none()
+            && self.governance_id.is_none()
+            && self.schema_id.is_none()
+            && self.requester.is_none()
+            && self.gov_version.is_none()
+    }
+}
+
+impl From<&GetApprovalsQuery> for ApprovalFilterBody {
+    fn from(value: &GetApprovalsQuery) -> Self {
+        Self {
+            subject_id: value.subject_id.clone(),
+            governance_id: value.governance_id.clone(),
+            schema_id: value.schema_id.clone(),
+            requester: value.requester.clone(),
+            gov_version: value.gov_version,
+        }
+    }
+}
+
+/// Selects the requests for approval that match a filter. The subjects of the requests are
+/// fetched once, and only when the filter needs their governance or schema
+pub struct ApprovalFilter<'a> {
+    node: &'a Api,
+    filter: ApprovalFilterBody,
+    subjects: HashMap<DigestIdentifier, Option<SubjectData>>,
+}
+
+impl<'a> ApprovalFilter<'a> {
+    pub fn new(node: &'a Api, filter: ApprovalFilterBody) -> Self {
+        Self {
+            node,
+            filter,
+            subjects: HashMap::new(),
+        }
+    }
+
+    pub async fn matches(&mut self, approval: &ApprovalEntity) -> bool {
+        let request = &approval.request.content;
+        // Only Fact requests need approval
+        let EventRequest::Fact(fact) = &request.event_request.content else {
+            return false;
+        };
+        let filter = &self.filter;
+        let simple_match = filter
+            .subject_id
+            .as_ref()
+            .map_or(true, |id| *id == fact.subject_id.to_str())
+            && filter.requester.as_ref().map_or(true, |requester| {
+                *requester == request.event_request.signature.signer.to_str()
+            })
+            && filter
+                .gov_version
+                .map_or(true, |version| version == request.gov_version);
+        if !simple_match {
+            return false;
+        }
+        if filter.governance_id.is_none() && filter.schema_id.is_none() {
+            return true;
+        }
+        let (governance_id, schema_id) = (filter.governance_id.clone(), filter.schema_id.clone());
+        let Some(subject) = self.subject(&fact.subject_id).await else {
+            return false;
+        };
+        let governance_matches = governance_id.map_or(true, |id| {
+            if subject.schema_id == GOVERNANCE_SCHEMA {
+                id == subject.subject_id.to_str()
+            } else {
+                id == subject.governance_id.to_str()
+            }
+        });
+        governance_matches && schema_id.map_or(true, |id| id == subject.schema_id)
+    }
+
+    async fn subject(&mut self, id: &DigestIdentifier) -> Option<&SubjectData> {
+        if !self.subjects.contains_key(id) {
+            let subject = self.node.get_subject(id.clone()).await.ok();
+            self.subjects.insert(id.clone(), subject);
+        }
+        self.subjects.get(id).and_then(Option::as_ref)
+    }
+
+    /// Requests for approval in the given state that match the filter, walking the list of the
+    /// node after `from`. A negative quantity walks it backwards
+    pub async fn approvals(
+        &mut self,
+        status: Option<ApprovalState>,
+        from: Option<String>,
+        quantity: Option<i64>,
+    ) -> Result<Vec<ApprovalEntity>, ApiError> {
+        let quantity = quantity.filter(|quantity| *quantity != 0);
+        let limit = quantity.map(|quantity| quantity.unsigned_abs() as usize);
+        let mut batches = Batches::new(from, quantity.map_or(false, |quantity| quantity < 0));
+        let mut approvals = Vec::new();
+        loop {
+            let Some(batch) = batches
+                .next(
+                    |cursor, size| self.node.get_approvals(status.clone(), cursor, Some(size)),
+                    |approval: &ApprovalEntity| approval.id.to_str(),
+                )
+                .await?
+            else {
+                return Ok(approvals);
+            };
+            for approval in batch {
+                if self.matches(&approval).await {
+                    approvals.push(approval);
+                }
+                if limit.map_or(false, |limit| approvals.len() >= limit) {
+                    return Ok(approvals);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::{collections::HashMap, sync::Arc};
+
+use sha2::{Digest, Sha256};
+use warp::{http::HeaderMap, Filter, Rejection};
+
+use super::error::Error;
+use crate::{
+    http::tls::ClientIdentity,
+    settings::{ApiCredential, ApiRole},
+};
+
+/// Kind of operation performed by a route
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    /// Queries of the node state
+    Read,
+    /// Submission of event requests
+    Submit,
+    /// Votes of approval requests
+    Approve,
+    /// Management of the node, such as keys and preauthorized subjects
+    Admin,
+}
+
+fn grants(role: ApiRole, scope: Scope) -> bool {
+    match role {
+        ApiRole::Admin => true,
+        ApiRole::Approver => matches!(scope, Scope::Read | Scope::Approve),
+        ApiRole::Submitter => matches!(scope, Scope::Read | Scope::Submit),
+        ApiRole::ReadOnly => scope == Scope::Read,
+    }
+}
+
+/// Credential with which a request was authenticated
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Credential {
+    /// Every request when the API is left open
+    Anonymous,
+    ApiKey(String),
+    /// Common Name of a verified client certificate
+    ClientCertificate(String),
+}
+
+impl Credential {
+    /// Identifier of the credential that does not disclose the API key
+    pub fn id(&self) -> String {
+        let value = match self {
+            Credential::Anonymous => return "anonymous".to_owned(),
+            Credential::ApiKey(key) => format!("key:{}", key),
+            Credential::ClientCertificate(name) => format!("cn:{}", name),
+        };
+        hex::encode(Sha256::digest(value.as_bytes()))
+    }
+}
+
+/// Credentials accepted by the REST API: API keys and identities of client certificates.
+/// When none is configured the API is left open.
+#[derive(Clone, Debug)]
+pub struct ApiKeys {
+    header: String,
+    keys: Arc<HashMap<String, ApiRole>>,
+    identities: Arc<HashMap<String, ApiRole>>,
+}
+
+fn roles(credentials: Vec<ApiCredential>) -> Arc<HashMap<String, ApiRole>> {
+    Arc::new(
+        credentials
+            .into_iter()
+            .map(|credential| (credential.key, credential.role))
+            .collect(),
+    )
+}
+
+impl ApiKeys {
+    pub fn new(header: String, credentials: Vec<ApiCredential>) -> Self {
+        Self {
+            header,
+            keys: roles(credentials),
+            identities: Arc::new(HashMap::new()),
+        }
+    }
+
+    /// Accepts the verified client certificates with the given Common Names
+    pub fn with_client_identities(mut self, identities: Vec<ApiCredential>) -> Self {
+        self.identities = roles(identities);
+        self
+    }
+
+    /// API keys that let every request through
+    pub fn disabled() -> Self {
+        Self::new(String::new(), Vec::new())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty() || !self.identities.is_empty()
+    }
+
+    fn authorize(
+        &self,
+        headers: &HeaderMap,
+        identity: Option<ClientIdentity>,
+        scope: Scope,
+    ) -> Result<Credential, Error> {
+        if !self.is_enabled() {
+            return Ok(Credential::Anonymous);
+        }
+        match self.authenticate(headers, identity) {
+            None => Err(Error::Unauthorized {
+                error: "Missing or invalid credentials".to_owned(),
+            }),
+            Some((_, role)) if !grants(role, scope) => Err(Error::NotEnoughPermissions {
+                error: format!("{:?} credentials can not access this resource", role),
+            }),
+            Some((credential, _)) => Ok(credential),
+        }
+    }
+
+    fn authenticate(
+        &self,
+        headers: &HeaderMap,
+        identity: Option<ClientIdentity>,
+    ) -> Option<(Credential, ApiRole)> {
+        // An API key takes precedence over the client certificate
+        let key = headers
+            .get(&self.header)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|key| {
+                self.role(key)
+                    .map(|role| (Credential::ApiKey(key.to_owned()), role))
+            });
+        key.or_else(|| {
+            identity.and_then(|identity| {
+                let role = self.identities.get(&identity.0).copied()?;
+                Some((Credential::ClientCertificate(identity.0), role))
+            })
+        })
+    }
+
+    fn role(&self, key: &str) -> Option<ApiRole> {
+        // Every key is compared in full so the response time does not leak partial matches
+        self.keys.iter().fold(None, |found, (valid, role)| {
+            if constant_time_eq(valid, key) {
+                Some(*role)
+            } else {
+                found
+            }
+        })
+    }
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.bytes()
+        .zip(b.bytes())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+/// Rejects the request unless it carries credentials allowed to operate on the given scope
+pub fn with_scope(
+    api_keys: ApiKeys,
+    scope: Scope,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    with_credential(api_keys, scope).map(|_| ()).untuple_one()
+}
+
+/// Like [`with_scope`], but extracts the credential with which the request was authenticated
+pub fn with_credential(
+    api_keys: ApiKeys,
+    scope: Scope,
+) -> impl Filter<Extract = (Credential,), Error = Rejection> + Clone {
+    warp::header::headers_cloned()
+        .and(warp::ext::optional::<ClientIdentity>())
+        .and(warp::any().map(move || api_keys.clone()))
+        .and_then(
+            move |headers: HeaderMap, identity: Option<ClientIdentity>, api_keys: ApiKeys| async move {
+                api_keys
+                    .authorize(&headers, identity, scope)
+                    .map_err(warp::reject::custom)
+            },
+        )
+}
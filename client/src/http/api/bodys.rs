@@ -11,8 +11,11 @@ use taple_core::{
     signature::{Signature, Signed},
     ApiError, TimeStamp, ValueWrapper,
 };
+use taple_verification::Quorum;
 use utoipa::ToSchema;
 
+use super::responses::ValidationProofResponse;
+
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct SignedBody<T>
 where
@@ -235,12 +238,68 @@ pub struct AuthorizeSubjectBody {
     pub providers: Vec<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DesiredStateRequestBody {
+    /// Subject identifier
+    pub subject_id: String,
+    /// State the subject must have after the event
+    pub state: Value,
+    /// Method of the contract that receives the patch. "Patch" if absent
+    pub method: Option<String>,
+    /// Identity of the client that signs the request. The one of the node if absent
+    pub identity: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PostWebhookBody {
+    /// URL that receives the notifications
+    pub url: String,
+    /// Secret used to sign the deliveries. If absent, the shared secret of the settings is used
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VerifyProofBody {
+    /// Validation proof, as returned by the validation endpoint of the subject
+    pub proof: ValidationProofResponse,
+    /// Signatures of the validators over the proof
+    pub signatures: Vec<SignatureBody>,
+    /// Public keys of the validators. They are not checked against the governance
+    pub validators: Vec<String>,
+    /// Signatures required. Majority if absent
+    pub quorum: Option<QuorumBody>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum QuorumBody {
+    /// More than half of the validators
+    Majority,
+    /// A fixed number of validators, at least one
+    Fixed(u32),
+    /// A fraction, greater than 0 and up to 1, of the validators
+    Percentage(f64),
+}
+
+impl From<QuorumBody> for Quorum {
+    fn from(value: QuorumBody) -> Self {
+        match value {
+            QuorumBody::Majority => Quorum::Majority,
+            QuorumBody::Fixed(required) => Quorum::Fixed(required),
+            QuorumBody::Percentage(fraction) => Quorum::Percentage(fraction),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PostEventRequestBodyPreSignature {
     /// Type of event request
     pub request: EventRequestBody,
     /// Signature of the issuer
     pub signature: Option<SignatureBody>,
+    /// Identity of the client that signs the request when it is not signed. The one of the node if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -286,6 +345,29 @@ impl TryInto<Signature> for SignatureBody {
     }
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ApprovalFilterBody {
+    /// Subject of the event request
+    pub subject_id: Option<String>,
+    /// Governance of the subject. Requests about the governance itself are included
+    pub governance_id: Option<String>,
+    /// Schema of the subject
+    pub schema_id: Option<String>,
+    /// Signer of the event request
+    pub requester: Option<String>,
+    /// Version of the governance with which the request was evaluated
+    pub gov_version: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkVoteBody {
+    /// Requests for approval to vote
+    pub ids: Option<Vec<String>>,
+    /// Vote every pending request for approval that matches the filter instead. The filter must set at least one criterion
+    pub filter: Option<ApprovalFilterBody>,
+    pub vote: PatchVoteBody,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "state")]
 pub enum PatchVoteBody {
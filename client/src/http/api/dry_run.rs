@@ -0,0 +1,229 @@
+use std::str::FromStr;
+
+use serde_json::Value;
+use taple_core::{
+    identifier::{Derivable, DigestIdentifier, KeyIdentifier},
+    request::EventRequest,
+    signature::{Signature, Signed},
+    Api, KeyDerivator, SubjectData,
+};
+
+use super::{
+    bodys::{EventRequestBody, PostEventRequestBodyPreSignature},
+    governance::GOVERNANCE_SCHEMA,
+    responses::{DryRunProblem, DryRunResponse, SubjectDataResponse},
+    schema,
+};
+
+/// Checks an event request as the node would on submission, without submitting it.
+/// Every problem found is reported instead of stopping at the first one
+pub async fn dry_run(node: &Api, body: PostEventRequestBodyPreSignature) -> DryRunResponse {
+    let mut check = DryRun {
+        node,
+        problems: Vec::new(),
+        subject: None,
+        governance_id: None,
+        resulting_state: None,
+    };
+    let request = check.request(&body.request);
+    match &body.request {
+        EventRequestBody::Create(request) => {
+            check
+                .governance(&request.governance_id, Some(&request.schema_id))
+                .await;
+            if request.name.is_empty() {
+                check.problem("name", "The subject must have a name");
+            }
+        }
+        EventRequestBody::Fact(request) => {
+            if let Some(subject) = check.subject(&request.subject_id).await {
+                check.patched_state(&subject, &request.payload).await;
+            }
+        }
+        EventRequestBody::Transfer(request) => {
+            check.subject(&request.subject_id).await;
+        }
+        EventRequestBody::EOL(request) => {
+            check.subject(&request.subject_id).await;
+        }
+    }
+    check.signature(&body, request);
+    DryRunResponse {
+        valid: check.problems.is_empty(),
+        problems: check.problems,
+        subject: check.subject.map(SubjectDataResponse::from),
+        governance_id: check.governance_id,
+        resulting_state: check.resulting_state,
+    }
+}
+
+struct DryRun<'a> {
+    node: &'a Api,
+    problems: Vec<DryRunProblem>,
+    subject: Option<SubjectData>,
+    governance_id: Option<String>,
+    resulting_state: Option<Value>,
+}
+
+impl<'a> DryRun<'a> {
+    fn problem(&mut self, field: &str, error: impl Into<String>) {
+        self.problems.push(DryRunProblem {
+            field: field.to_owned(),
+            error: error.into(),
+        });
+    }
+
+    /// Converts the request as it is done on submission, which reports the identifiers that
+    /// are not valid. Unsigned creation requests without public key are given one when they
+    /// are submitted, so a placeholder takes its place
+    fn request(&mut self, request: &EventRequestBody) -> Option<EventRequest> {
+        let mut request = request.clone();
+        if let EventRequestBody::Create(create) = &mut request {
+            if create.public_key.is_none() {
+                create.public_key = Some(placeholder_key());
+            }
+        }
+        match request.try_into() {
+            Ok(request) => Some(request),
+            Err(error) => {
+                self.problem("request", error.to_string());
+                None
+            }
+        }
+    }
+
+    /// Resolves the subject of the request, which must be active
+    async fn subject(&mut self, subject_id: &str) -> Option<SubjectData> {
+        // Invalid identifiers are reported by the conversion of the request
+        let id = DigestIdentifier::from_str(subject_id).ok()?;
+        let subject = match self.node.get_subject(id).await {
+            Ok(subject) => subject,
+            Err(error) => {
+                self.problem("subject_id", error.to_string());
+                return None;
+            }
+        };
+        if !subject.active {
+            self.problem("subject_id", "The subject has reached its end of life");
+        }
+        if subject.schema_id == GOVERNANCE_SCHEMA {
+            self.governance_id = Some(subject.subject_id.to_str());
+        } else {
+            let governance_id = subject.governance_id.to_str();
+            self.governance(&governance_id, None).await;
+        }
+        self.subject = Some(subject.clone());
+        Some(subject)
+    }
+
+    /// Resolves a governance and, if given, checks that it defines the schema
+    async fn governance(&mut self, governance_id: &str, schema_id: Option<&str>) {
+        if schema_id == Some(GOVERNANCE_SCHEMA) && governance_id.is_empty() {
+            // Governances are created without governance
+            return;
+        }
+        let Ok(id) = DigestIdentifier::from_str(governance_id) else {
+            return;
+        };
+        self.governance_id = Some(governance_id.to_owned());
+        let governance = match self.node.get_subject(id).await {
+            Ok(governance) => governance,
+            Err(error) => {
+                self.problem("governance_id", error.to_string());
+                return;
+            }
+        };
+        if governance.schema_id != GOVERNANCE_SCHEMA {
+            self.problem("governance_id", "The subject is not a governance");
+            return;
+        }
+        let Some(schema_id) = schema_id else {
+            return;
+        };
+        let defined = governance.properties.0["schemas"]
+            .as_array()
+            .map(|schemas| schemas.iter().any(|schema| schema["id"] == schema_id))
+            .unwrap_or(false);
+        if !defined {
+            self.problem(
+                "schema_id",
+                format!("The governance does not define the schema {}", schema_id),
+            );
+        }
+    }
+
+    /// Applies the patch of a payload in the `{"<method>": {"data": <patch>}}` form and checks the
+    /// resulting state against the schema of the subject
+    async fn patched_state(&mut self, subject: &SubjectData, payload: &Value) {
+        let Some(patch) = schema::payload_patch(payload) else {
+            return;
+        };
+        let mut state = subject.properties.0.clone();
+        if let Err(error) = json_patch::patch(&mut state, &patch) {
+            self.problem("payload", error.to_string());
+            return;
+        }
+        for violation in schema::schema_violations(self.node, subject, &state).await {
+            self.problem(
+                "payload",
+                format!("{}: {}", violation.path, violation.error),
+            );
+        }
+        self.resulting_state = Some(state);
+    }
+
+    /// Verifies the signature, if any, and that the signer can issue the request
+    fn signature(
+        &mut self,
+        body: &PostEventRequestBodyPreSignature,
+        request: Option<EventRequest>,
+    ) {
+        let Some(signature) = body.signature.clone() else {
+            return;
+        };
+        let signature: Signature = match signature.try_into() {
+            Ok(signature) => signature,
+            Err(error) => {
+                self.problem("signature", error.to_string());
+                return;
+            }
+        };
+        if let EventRequestBody::Create(create) = &body.request {
+            if create.public_key.is_none() {
+                self.problem(
+                    "signature",
+                    "A signed creation request must include the public key of the subject",
+                );
+                return;
+            }
+        }
+        // Problems of the request itself have already been reported
+        let Some(request) = request else {
+            return;
+        };
+        let signed = Signed {
+            content: request,
+            signature,
+        };
+        if let Err(error) = signed.verify() {
+            self.problem("signature", error.to_string());
+        }
+        let owner_only = matches!(
+            body.request,
+            EventRequestBody::Transfer(_) | EventRequestBody::EOL(_)
+        );
+        if let Some(subject) = self.subject.as_ref().filter(|_| owner_only) {
+            if signed.signature.signer != subject.owner {
+                self.problem(
+                    "signature",
+                    "Only the owner of the subject can sign this request",
+                );
+            }
+        }
+    }
+}
+
+/// Public key that stands for the one generated on submission
+fn placeholder_key() -> String {
+    KeyIdentifier::new(KeyDerivator::Ed25519, &[0u8; 32]).to_str()
+}
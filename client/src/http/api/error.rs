@@ -1,6 +1,10 @@
 use taple_core::ApiError;
 use thiserror::Error;
-use warp::reject;
+use warp::{hyper::StatusCode, reject};
+
+use super::responses::SchemaViolationResponse;
+use crate::keys::KeyRegistryError;
+use crate::webhooks::WebhookError;
 
 #[allow(dead_code)]
 #[derive(Error, Debug, Clone)]
@@ -24,6 +28,95 @@ pub enum Error {
     Unauthorized { error: String },
     #[error("Conflict: {}", error)]
     Conflict { error: String },
+    #[error("The resulting state does not match the schema of the subject")]
+    SchemaViolations {
+        violations: Vec<SchemaViolationResponse>,
+    },
 }
 
 impl reject::Reject for Error {}
+
+impl Error {
+    /// Translates an error of the node into the one reported by the API REST
+    pub fn from_api_error(error: ApiError) -> Self {
+        match error {
+            ApiError::InvalidParameters(error) => Error::InvalidParameters { error },
+            ApiError::Conflict(error) => Error::Conflict { error },
+            ApiError::NotFound(error) => Error::NotFound { error },
+            ApiError::NotEnoughPermissions(error) => Error::NotEnoughPermissions { error },
+            source => Error::ExecutionError { source },
+        }
+    }
+
+    /// Translates an error of the webhooks registry
+    pub fn from_webhook_error(error: WebhookError) -> Self {
+        match error {
+            WebhookError::InvalidUrl(_) => Error::InvalidParameters {
+                error: error.to_string(),
+            },
+            WebhookError::AlreadyRegistered(_) | WebhookError::Configured(_) => Error::Conflict {
+                error: error.to_string(),
+            },
+            WebhookError::NotFound(_) => Error::NotFound {
+                error: error.to_string(),
+            },
+            WebhookError::Database(_) => Error::InternalServerError {
+                error: error.to_string(),
+            },
+        }
+    }
+
+    /// Translates an error of the key registry
+    pub fn from_key_error(error: KeyRegistryError) -> Self {
+        match error {
+            KeyRegistryError::NotFound(_) => Error::NotFound {
+                error: error.to_string(),
+            },
+            KeyRegistryError::AlreadyRetired(_) | KeyRegistryError::InUse(..) => Error::Conflict {
+                error: error.to_string(),
+            },
+            KeyRegistryError::Node(_) | KeyRegistryError::Database(_) => {
+                Error::InternalServerError {
+                    error: error.to_string(),
+                }
+            }
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Error::InternalServerError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::ExecutionError { .. } => StatusCode::CONFLICT,
+            Error::InvalidParameters { .. } => StatusCode::BAD_REQUEST,
+            Error::NotEnoughPermissions { .. } => StatusCode::FORBIDDEN,
+            Error::NotFound { .. } => StatusCode::NOT_FOUND,
+            Error::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
+            Error::BadRequest { .. } => StatusCode::BAD_REQUEST,
+            Error::Conflict { .. } => StatusCode::CONFLICT,
+            Error::SchemaViolations { .. } => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    /// Message sent to the client
+    pub fn message(&self) -> String {
+        match self {
+            Error::InternalServerError { error } => error.to_owned(),
+            Error::ExecutionError { source } => source.to_string(),
+            Error::InvalidParameters { error }
+            | Error::NotEnoughPermissions { error }
+            | Error::NotFound { error }
+            | Error::Unauthorized { error }
+            | Error::BadRequest { error }
+            | Error::Conflict { error } => error.to_string(),
+            Error::SchemaViolations { .. } => self.to_string(),
+        }
+    }
+
+    /// Values of the request that do not match the schema of the subject
+    pub fn violations(&self) -> Vec<SchemaViolationResponse> {
+        match self {
+            Error::SchemaViolations { violations } => violations.clone(),
+            _ => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use serde::de::DeserializeOwned;
+use taple_core::{
+    identifier::{Derivable, DigestIdentifier},
+    Api, SubjectData,
+};
+
+use super::{
+    error::Error,
+    querys::{GetGovernanceMembersQuery, GetGovernancePoliciesQuery, GetGovernanceRolesQuery},
+    responses::{
+        GovernanceMemberResponse, GovernancePolicyResponse, GovernanceRoleResponse,
+        GovernanceSchemaResponse, RoleSchemaResponse, RoleWhoResponse,
+    },
+};
+
+/// Schema of the subjects that are governances
+pub const GOVERNANCE_SCHEMA: &str = "governance";
+
+/// Typed view of the document of a governance subject
+pub struct Governance(SubjectData);
+
+impl Governance {
+    /// Fetches a subject, which must be a governance
+    pub async fn get(node: &Api, id: DigestIdentifier) -> Result<Self, Error> {
+        let subject = node.get_subject(id).await.map_err(Error::from_api_error)?;
+        if subject.schema_id != GOVERNANCE_SCHEMA {
+            return Err(Error::InvalidParameters {
+                error: format!(
+                    "Subject {} is not a governance",
+                    subject.subject_id.to_str()
+                ),
+            });
+        }
+        Ok(Self(subject))
+    }
+
+    fn section<T: DeserializeOwned>(&self, name: &str) -> Result<Vec<T>, Error> {
+        serde_json::from_value(self.0.properties.0[name].clone()).map_err(|error| {
+            Error::InternalServerError {
+                error: format!(
+                    "Invalid {} in governance {}: {}",
+                    name,
+                    self.0.subject_id.to_str(),
+                    error
+                ),
+            }
+        })
+    }
+
+    pub fn members(
+        &self,
+        query: &GetGovernanceMembersQuery,
+    ) -> Result<Vec<GovernanceMemberResponse>, Error> {
+        let members: Vec<GovernanceMemberResponse> = self.section("members")?;
+        if query.role.is_none() && query.schema.is_none() {
+            return Ok(members);
+        }
+        let roles: Vec<GovernanceRoleResponse> = self.section("roles")?;
+        let roles: Vec<&GovernanceRoleResponse> = roles
+            .iter()
+            .filter(|role| role_matches(role, query.role.as_deref(), query.schema.as_deref()))
+            .collect();
+        Ok(members
+            .iter()
+            .filter(|member| {
+                roles
+                    .iter()
+                    .any(|role| who_matches(&role.who, &member.id, Some(member)))
+            })
+            .cloned()
+            .collect())
+    }
+
+    pub fn roles(
+        &self,
+        query: &GetGovernanceRolesQuery,
+    ) -> Result<Vec<GovernanceRoleResponse>, Error> {
+        let roles: Vec<GovernanceRoleResponse> = self.section("roles")?;
+        let members: Vec<GovernanceMemberResponse> = match query.member {
+            Some(_) => self.section("members")?,
+            None => Vec::new(),
+        };
+        // The member may be given by public key or by name, and may not be a member at all
+        let member = query.member.as_deref().map(|member| {
+            let found = members
+                .iter()
+                .find(|candidate| candidate.id == member || candidate.name == member);
+            (member, found)
+        });
+        Ok(roles
+            .into_iter()
+            .filter(|role| role_matches(role, query.role.as_deref(), query.schema.as_deref()))
+            .filter(|role| match member {
+                Some((member, found)) => who_matches(&role.who, member, found),
+                None => true,
+            })
+            .collect())
+    }
+
+    pub fn schemas(&self) -> Result<Vec<GovernanceSchemaResponse>, Error> {
+        self.section("schemas")
+    }
+
+    pub fn policies(
+        &self,
+        query: &GetGovernancePoliciesQuery,
+    ) -> Result<Vec<GovernancePolicyResponse>, Error> {
+        let policies: Vec<GovernancePolicyResponse> = self.section("policies")?;
+        Ok(policies
+            .into_iter()
+            .filter(|policy| match &query.schema {
+                Some(schema) => &policy.id == schema,
+                None => true,
+            })
+            .collect())
+    }
+}
+
+fn role_matches(role: &GovernanceRoleResponse, name: Option<&str>, schema: Option<&str>) -> bool {
+    let name_matches = name.map_or(true, |name| role.role.eq_ignore_ascii_case(name));
+    let schema_matches = schema.map_or(true, |schema| match &role.schema {
+        RoleSchemaResponse::Id(id) => id == schema,
+        RoleSchemaResponse::NotGovernance => schema != GOVERNANCE_SCHEMA,
+        RoleSchemaResponse::All => true,
+    });
+    name_matches && schema_matches
+}
+
+/// Whether a role is held by someone, identified by public key or name. `member` is the
+/// member of the governance it corresponds to, if any
+fn who_matches(
+    who: &RoleWhoResponse,
+    identifier: &str,
+    member: Option<&GovernanceMemberResponse>,
+) -> bool {
+    match who {
+        RoleWhoResponse::Id(id) => id == identifier || member.map_or(false, |m| &m.id == id),
+        RoleWhoResponse::Name(name) => {
+            name == identifier || member.map_or(false, |m| &m.name == name)
+        }
+        RoleWhoResponse::Members => member.is_some(),
+        RoleWhoResponse::All => true,
+        RoleWhoResponse::NotMembers => member.is_none(),
+    }
+}
@@ -1,38 +1,110 @@
-use std::{collections::HashSet, str::FromStr};
+use std::{collections::HashSet, str::FromStr, time::Duration};
 
+use futures::{SinkExt, StreamExt};
 use serde::Serialize;
 use serde_json::Value;
 use taple_core::{
-    crypto::KeyPair,
     identifier::{Derivable, DigestIdentifier},
-    signature::{Signature, Signed},
-    ApprovalState, KeyDerivator, KeyIdentifier, DigestDerivator,
+    request::{RequestState, TapleRequest},
+    signature::Signature,
+    ApprovalState, KeyIdentifier, ValidationProof,
+};
+use taple_verification::Quorum;
+use warp::{
+    hyper::StatusCode,
+    ws::{Message, Ws},
+    Rejection,
 };
-use warp::Rejection;
 
 use taple_core::{Api, ApiError};
 
+use crate::approvals::ApprovalPolicy;
+use crate::http::api::querys::AddKeysQuery;
 use crate::http::api::querys::GetWithPaginationString;
-use crate::{http::api::querys::AddKeysQuery, http::api::querys::KeyAlgorithms};
+use crate::keys::{KeyAlgorithms, KeyRegistry};
+use crate::notifications::{notification_kind, NotificationFilter, NotificationHub};
+use crate::webhooks::Webhooks;
 
 use super::{
+    approval_filter::ApprovalFilter,
     bodys::{
-        self, AuthorizeSubjectBody, PatchVoteBody, PostEventRequestBodyPreSignature, SignatureBody,
-        SignedBody,
+        ApprovalFilterBody, AuthorizeSubjectBody, BulkVoteBody, DesiredStateRequestBody,
+        PatchVoteBody, PostEventRequestBodyPreSignature, PostWebhookBody, SignatureBody,
+        SignedBody, VerifyProofBody,
     },
+    dry_run,
     error::Error,
-    querys::{GetAllSubjectsQuery, GetApprovalsQuery, GetWithPagination},
+    governance::Governance,
+    history,
+    idempotency::{IdempotencyKey, IdempotencyStore, Lookup},
+    pagination::{handle_page, PageRequest},
+    querys::{
+        GetAllSubjectsQuery, GetApprovalsQuery, GetGovernanceMembersQuery,
+        GetGovernancePoliciesQuery, GetGovernanceRolesQuery, GetKeysQuery, GetNotificationsQuery,
+        GetSubjectStateQuery, GetWithPagination, WaitQuery,
+    },
     responses::{
-        ApprovalEntityResponse, EventContentResponse, GetProofResponse,
-        PreauthorizedSubjectsResponse, SignedEvent, SubjectDataResponse, TapleRequestResponse,
-        TapleRequestStateResponse, ValidationProofResponse,
+        ApprovalDecisionResponse, ApprovalEntityResponse, ApprovalVoteResultResponse,
+        DesiredStateResponse, DryRunResponse, ErrorResponse, EventContentResponse,
+        EventRequestResultResponse, GetProofResponse, IdentityResponse, KeyDetailsResponse,
+        KeyResponse, NotificationResponse, PreauthorizedSubjectsResponse,
+        ProofVerificationResponse, SignedEvent, SubjectDataResponse, SubjectStateResponse,
+        TapleRequestResponse, TapleRequestStateResponse, ValidationProofResponse,
+        WebhookDeliveryResponse, WebhookResponse,
     },
+    submission::{select_identity, wait_for_request, EventRequestSubmitter},
 };
 
+/// Maximum number of event requests accepted in a batch
+pub const MAX_BATCH_SIZE: usize = 1000;
+/// Maximum number of requests for approval voted at once
+pub const MAX_BULK_VOTE_SIZE: usize = 1000;
+
+/// Get automatic vote of an approval
+///
+/// Allows to obtain the vote cast on a request for approval by the approval policy of the node, with the rule that matched the request.
+#[utoipa::path(
+    get,
+    path = "/approval-requests/{id}/decision",
+    operation_id = "Get Approval Decision",
+    tag = "Approvals",
+    context_path = "/api",
+    params(
+        ("id" = String, Path, description = "Approval's unique id"),
+    ),
+    responses(
+        (status = 200, description = "Vote of the approval policy", body = ApprovalDecisionResponse,
+        example = json!(
+            {
+                "approval_id": "J8618wGO7hH4wRuEeL0Ob5XNI9Q73BlCNlV8cWBORq78",
+                "subject_id": "JoifaSpfenD2bEPeBLvUTWh30brm4tKcvdW8exQnkGoQ",
+                "sn": 3,
+                "rule": "harvest-updates",
+                "approved": true,
+                "decided_at": 1688643031,
+                "error": null
+            }
+        )),
+        (status = 404, description = "The request was not voted by the approval policy"),
+    )
+)]
+pub async fn get_approval_decision_handler(
+    id: String,
+    approvals: ApprovalPolicy,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    match approvals.decision(&id) {
+        Some(decision) => handle_data(Ok(ApprovalDecisionResponse::from(decision))),
+        None => Err(warp::reject::custom(Error::NotFound {
+            error: format!("Decision of request for approval {}", id),
+        })),
+    }
+}
+
 /// Get approvals
 ///
 /// Allows to obtain the list of requests for approvals received by the node.
 /// It can also be used, by means of the "status" parameter, to list the requests pending approval.
+/// The requests can also be filtered by subject, governance, schema, requester and governance version.
 #[utoipa::path(
     get,
     path = "/approval-requests",
@@ -42,9 +114,15 @@ use super::{
     params(
         ("id" = String, Path, description = "Approval's unique id"),
         ("status" = Option<String>, Query, description = "Approval's status (possibilities: pending, obsolete, responded)"),
+        ("subject_id" = Option<String>, Query, description = "Subject of the event request"),
+        ("governance_id" = Option<String>, Query, description = "Governance of the subject. Requests about the governance itself are included"),
+        ("schema_id" = Option<String>, Query, description = "Schema of the subject"),
+        ("requester" = Option<String>, Query, description = "Signer of the event request"),
+        ("gov_version" = Option<u64>, Query, description = "Version of the governance with which the request was evaluated"),
         ("from" = Option<String>, Query, description = "Id of initial approval"),
-        ("quantity" = Option<isize>, Query, description = "Quantity of approvals requested"
-    )),
+        ("quantity" = Option<isize>, Query, description = "Quantity of approvals requested"),
+        ("envelope" = Option<bool>, Query, description = "Wrap the entries in a page with the cursors of the adjacent pages, also sent in the Link header"),
+    ),
     responses(
         (status = 200, description = "Approvals Data successfully retrieved", body = [ApprovalEntityResponse],
         example = json!(
@@ -123,16 +201,27 @@ pub async fn get_approvals_handler(
             }
         },
     };
-    let data = node
-        .get_approvals(status, parameters.from, parameters.quantity)
-        .await
-        .map(|result| {
-            result
-                .into_iter()
-                .map(ApprovalEntityResponse::from)
-                .collect::<Vec<ApprovalEntityResponse>>()
-        });
-    handle_data(data)
+    let page = PageRequest::new(
+        parameters.envelope,
+        parameters.from.is_some(),
+        parameters.quantity,
+    );
+    let filter = ApprovalFilterBody::from(&parameters);
+    let data = if filter.is_empty() {
+        node.get_approvals(status, parameters.from.clone(), page.quantity())
+            .await
+    } else {
+        ApprovalFilter::new(&node, filter)
+            .approvals(status, parameters.from.clone(), page.quantity())
+            .await
+    }
+    .map(|result| {
+        result
+            .into_iter()
+            .map(ApprovalEntityResponse::from)
+            .collect::<Vec<ApprovalEntityResponse>>()
+    });
+    handle_page(data, page, &parameters)
 }
 
 /// Get approval by ID
@@ -329,6 +418,97 @@ pub async fn patch_approval_handler(
     handle_data(result)
 }
 
+/// Vote several requests for approval
+///
+/// Casts the same vote on a list of requests for approval, or on every pending request that matches a filter with at least one criterion.
+/// The failure of one of the votes does not prevent the rest.
+#[utoipa::path(
+    patch,
+    path = "/approval-requests",
+    operation_id = "Set Approvals",
+    tag = "Approvals",
+    context_path = "/api",
+    request_body(content = BulkVoteBody, content_type = "application/json", description = "Requests to vote, by id or by filter, and the vote"),
+    responses(
+        (status = 200, description = "Result of each vote", body = [ApprovalVoteResultResponse],
+        example = json!(
+            [
+                {
+                    "id": "J5dfpH-ahrqSo-od4jyZkubyO-XWFJSQ9maK73jKI4Ao",
+                    "state": "RespondedAccepted"
+                },
+                {
+                    "id": "JZ3nrgzYmEvK_dk2Da3LzGahQXw9Nhl5aAlCZU9rk8dI",
+                    "error": {
+                        "code": 409,
+                        "error": "Approval request is obsolete"
+                    }
+                }
+            ]
+        )),
+        (status = 400, description = "Bad Request"),
+        (status = 500, description = "Internal Server Error"),
+    )
+)]
+pub async fn patch_approvals_handler(
+    node: Api,
+    body: BulkVoteBody,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let acceptance = match body.vote {
+        PatchVoteBody::RespondedAccepted => true,
+        PatchVoteBody::RespondedRejected => false,
+    };
+    let ids: Vec<String> = match (body.ids, body.filter) {
+        (Some(ids), None) => ids,
+        (None, Some(filter)) if filter.is_empty() => {
+            return Err(warp::reject::custom(Error::InvalidParameters {
+                error: "The filter must select the requests for approval to vote".to_owned(),
+            }))
+        }
+        (None, Some(filter)) => {
+            // One more than the limit is requested to know if the filter selects too many
+            let approvals = ApprovalFilter::new(&node, filter)
+                .approvals(
+                    Some(ApprovalState::Pending),
+                    None,
+                    Some(MAX_BULK_VOTE_SIZE as i64 + 1),
+                )
+                .await;
+            match approvals {
+                Ok(approvals) => approvals
+                    .into_iter()
+                    .map(|approval| approval.id.to_str())
+                    .collect(),
+                Err(error) => return handle_data::<Value>(Err(error)),
+            }
+        }
+        _ => {
+            return Err(warp::reject::custom(Error::InvalidParameters {
+                error: "Either ids or filter must be given".to_owned(),
+            }))
+        }
+    };
+    if ids.len() > MAX_BULK_VOTE_SIZE {
+        return Err(warp::reject::custom(Error::InvalidParameters {
+            error: format!(
+                "A bulk vote can not include more than {} requests for approval",
+                MAX_BULK_VOTE_SIZE
+            ),
+        }));
+    }
+    let mut results = Vec::with_capacity(ids.len());
+    for id in ids {
+        let result = match DigestIdentifier::from_str(&id) {
+            Ok(digest) => node.approval_request(digest, acceptance).await,
+            Err(_) => Err(ApiError::InvalidParameters(
+                "ID specified is not a valid Digest Identifier".to_string(),
+            )),
+        };
+        results.push(ApprovalVoteResultResponse::from((id, result)));
+    }
+    Ok(Box::new(warp::reply::json(&results)))
+}
+
 /// Get authorized subjects
 ///
 /// Allows to obtain the list of subjects that have been pre-authorized by the node, as well as the identifiers of the nodes from which to obtain them.
@@ -340,7 +520,8 @@ pub async fn patch_approval_handler(
     context_path = "/api",
     params(
         ("from" = Option<String>, Query, description = "Id of initial subject"),
-        ("quantity" = Option<isize>, Query, description = "Quantity of subjects requested")
+        ("quantity" = Option<isize>, Query, description = "Quantity of subjects requested"),
+        ("envelope" = Option<bool>, Query, description = "Wrap the entries in a page with the cursors of the adjacent pages, also sent in the Link header"),
     ),
     responses(
         (status = 200, description = "Subject Data successfully retrieved", body = [PreauthorizedSubjectsResponse],
@@ -360,11 +541,16 @@ pub async fn get_allowed_subjects_handler(
     node: Api,
     parameters: GetWithPaginationString,
 ) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let page = PageRequest::new(
+        parameters.envelope,
+        parameters.from.is_some(),
+        parameters.quantity,
+    );
     let result = node
-        .get_all_allowed_subjects_and_providers(parameters.from, parameters.quantity)
+        .get_all_allowed_subjects_and_providers(parameters.from.clone(), page.quantity())
         .await
         .map(|x| Vec::from_iter(x.into_iter().map(PreauthorizedSubjectsResponse::from)));
-    handle_data(result)
+    handle_page(result, page, &parameters)
 }
 
 /// Set subject as preauthorized
@@ -436,6 +622,7 @@ pub async fn put_allowed_subjects_handler(
 /// Register KeyPair
 ///
 /// It allows to generate a pair of cryptographic keys in the node that can then be assigned to a subject. The private key is never revealed.
+/// The key is recorded in the key registry of the client.
 #[utoipa::path(
     post,
     path = "/keys",
@@ -458,6 +645,7 @@ pub async fn put_allowed_subjects_handler(
 )]
 pub async fn post_generate_keys_handler(
     node: Api,
+    keys: KeyRegistry,
     parameters: AddKeysQuery,
 ) -> Result<Box<dyn warp::Reply>, Rejection> {
     let derivator = parameters
@@ -465,18 +653,194 @@ pub async fn post_generate_keys_handler(
         .unwrap_or(KeyAlgorithms::Ed25519)
         .into();
     match node.add_keys(derivator).await {
-        Ok(key) => handle_data(Ok(serde_json::json!({
-            "public_key": key.to_str(),
-        }))),
+        Ok(key) => {
+            keys.record(&key, derivator);
+            handle_data(Ok(serde_json::json!({
+                "public_key": key.to_str(),
+            })))
+        }
         Err(error) => handle_data(Err::<Value, ApiError>(error)),
     }
 }
 
+/// Get keys
+///
+/// Lists the key pairs generated by the node through the client, with their algorithm.
+/// The keys of the subjects owned by the node are also recorded when the client starts, marked as backfilled.
+/// Keys generated before the client recorded them and never assigned to a subject are not listed.
+/// Retired keys are only included on request.
+#[utoipa::path(
+    get,
+    path = "/keys",
+    tag = "Others",
+    operation_id = "Get Keys",
+    context_path = "/api",
+    params(
+        ("retired" = Option<bool>, Query, description = "Include the retired keys"),
+    ),
+    responses(
+        (status = 200, description = "Keys of the node", body = [KeyResponse],
+        example = json!(
+            [
+                {
+                    "public_key": "ELZ_b-kZzdPykcYuRNC2ZZe_2lCTCUoo60GXfR4cuXMw",
+                    "derivator": "Ed25519",
+                    "created_at": 1688643031,
+                    "backfilled": false,
+                    "retired_at": null
+                }
+            ]
+        )),
+    )
+)]
+pub async fn get_keys_handler(
+    keys: KeyRegistry,
+    parameters: GetKeysQuery,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let keys: Vec<KeyResponse> = keys
+        .list(parameters.retired.unwrap_or(false))
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    handle_data(Ok(keys))
+}
+
+/// Get key
+///
+/// Allows to obtain a key generated by the node and the subjects whose public key it is.
+/// Only the keys listed in `GET /keys` are known.
+/// Subjects that were transferred have the key of their new owner and are not included.
+#[utoipa::path(
+    get,
+    path = "/keys/{public_key}",
+    tag = "Others",
+    operation_id = "Get Key",
+    context_path = "/api",
+    params(
+        ("public_key" = String, Path, description = "Public key of the key pair"),
+    ),
+    responses(
+        (status = 200, description = "Key and its subjects", body = KeyDetailsResponse,
+        example = json!(
+            {
+                "public_key": "ELZ_b-kZzdPykcYuRNC2ZZe_2lCTCUoo60GXfR4cuXMw",
+                "derivator": "Ed25519",
+                "created_at": 1688643031,
+                "backfilled": false,
+                "retired_at": null,
+                "subjects": [
+                    {
+                        "subject_id": "JoifaSpfenD2bEPeBLvUTWh30brm4tKcvdW8exQnkGoQ",
+                        "schema_id": "Wine",
+                        "name": "Wine_1",
+                        "active": true
+                    }
+                ]
+            }
+        )),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    )
+)]
+pub async fn get_key_handler(
+    public_key: String,
+    keys: KeyRegistry,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let details = match keys.get(&public_key) {
+        Ok(key) => keys
+            .subjects(&public_key)
+            .await
+            .map(|subjects| KeyDetailsResponse {
+                key: key.into(),
+                subjects: subjects.into_iter().map(Into::into).collect(),
+            }),
+        Err(error) => Err(error),
+    };
+    match details {
+        Ok(details) => handle_data(Ok(details)),
+        Err(error) => Err(warp::reject::custom(Error::from_key_error(error))),
+    }
+}
+
+/// Retire key
+///
+/// Retires a key that no longer controls any active subject, because they were transferred or reached their end of life.
+/// The node keeps the private key, but the key is no longer listed unless retired keys are requested.
+#[utoipa::path(
+    delete,
+    path = "/keys/{public_key}",
+    tag = "Others",
+    operation_id = "Retire Key",
+    context_path = "/api",
+    params(
+        ("public_key" = String, Path, description = "Public key of the key pair"),
+    ),
+    responses(
+        (status = 200, description = "Key retired", body = KeyResponse),
+        (status = 404, description = "Not Found"),
+        (status = 409, description = "The key is already retired or still controls active subjects"),
+        (status = 500, description = "Internal Server Error"),
+    )
+)]
+pub async fn delete_key_handler(
+    public_key: String,
+    keys: KeyRegistry,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    match keys.retire(&public_key).await {
+        Ok(key) => handle_data(Ok(KeyResponse::from(key))),
+        Err(error) => Err(warp::reject::custom(Error::from_key_error(error))),
+    }
+}
+
+/// Get identities
+///
+/// Lists the identities that can sign the event requests sent without signature, starting with the one of the node.
+/// They are selected with the identity field of the request or the Signing-Identity header.
+/// Identities are not tied to the API credentials: any credential allowed to submit requests can sign with any of them.
+#[utoipa::path(
+    get,
+    path = "/identities",
+    tag = "Others",
+    operation_id = "Get Identities",
+    context_path = "/api",
+    responses(
+        (status = 200, description = "Identities of the client", body = [IdentityResponse],
+        example = json!(
+            [
+                {
+                    "name": "node",
+                    "controller_id": "EbwR0yYrCYpTzlN5i5GX_MtAbKRw5y2euv3TdyvVYv7A"
+                },
+                {
+                    "name": "logistics",
+                    "controller_id": "ELZ_b-kZzdPykcYuRNC2ZZe_2lCTCUoo60GXfR4cuXMw"
+                }
+            ]
+        )),
+    )
+)]
+pub async fn get_identities_handler(
+    submitter: EventRequestSubmitter,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let identities: Vec<IdentityResponse> = submitter
+        .signers()
+        .list()
+        .into_iter()
+        .map(|(name, controller_id)| IdentityResponse {
+            name,
+            controller_id,
+        })
+        .collect();
+    handle_data(Ok(identities))
+}
+
 /// Send event request
 ///
 /// Allows to send an event request for a subject to the TAPLE node.
 /// These requests can be of any type of event (done, creation, transfer and end of life).
 /// In case of external invocation, the requests can be signed.
+/// Requests without signature are signed with the identity selected in the body or the Signing-Identity header, or with the one of the node.
+/// The state that a Fact request with a patch leads to is checked against the JSON Schema of the subject before submitting it.
 #[utoipa::path(
     post,
     path = "/event-requests",
@@ -484,6 +848,11 @@ pub async fn post_generate_keys_handler(
     operation_id = "createEventRequest",
     context_path = "/api",
     request_body = PostEventRequestBodyPreSignature,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Key identifying the request. Retries with the same key and body, sent with the same credential, get the response of the first request instead of submitting it again"),
+        ("Signing-Identity" = Option<String>, Header, description = "Identity of the client that signs the request when it is not signed"),
+        ("wait" = Option<u64>, Query, description = "Seconds, up to 60, to wait for the request to finish. The response is then the state of the request"),
+    ),
     responses(
         (status = 201, description = "Request Created Successfully", body = String,
         example = json!(
@@ -491,56 +860,253 @@ pub async fn post_generate_keys_handler(
                 "request_id": "J8618wGO7hH4wRuEeL0Ob5XNI9Q73BlCNlV8cWBORq78"
             }
         )),
-        (status = 400, description = "Bad Request"),
-        (status = 409, description = "Conflict"),
+        (status = 202, description = "The request is still being processed after waiting", body = TapleRequestStateResponse),
+        (status = 400, description = "Bad Request, also returned with the list of violations when the state a Fact request leads to does not match the schema of the subject", body = ErrorResponse),
+        (status = 409, description = "Conflict, also returned when the Idempotency-Key was used for a different request"),
         (status = 500, description = "Internal Server Error"),
     )
 )]
 pub async fn post_event_request_handler(
-    node: Api,
-    keys: KeyPair,
-    derivator: KeyDerivator,
-    digest_derivator: DigestDerivator,
+    idempotency_key: Option<IdempotencyKey>,
+    submitter: EventRequestSubmitter,
+    idempotency: IdempotencyStore,
+    notifications: NotificationHub,
+    identity: Option<String>,
+    parameters: WaitQuery,
     mut body: PostEventRequestBodyPreSignature,
 ) -> Result<Box<dyn warp::Reply>, Rejection> {
-    // If event request is a creation one and it does not specify a public_key, then a random one must be generated
-    if let bodys::EventRequestBody::Create(creation_req) = &mut body.request {
-        if creation_req.public_key.is_none() {
-            let public_key = node.add_keys(derivator).await;
-            if public_key.is_err() {
-                return handle_data(public_key);
+    body.identity =
+        select_identity(identity, body.identity.take()).map_err(warp::reject::custom)?;
+    let (result, replayed) = match idempotency_key {
+        None => (submitter.submit(body).await, false),
+        Some(idempotency_key) => {
+            let request = serde_json::to_string(&body).expect("Serialize event request");
+            let _guard = idempotency.lock(&idempotency_key).await;
+            match idempotency.lookup(&idempotency_key, &request) {
+                Ok(Lookup::Replay(id)) => (Ok(id), true),
+                Ok(Lookup::New) => {
+                    let result = submitter.submit(body).await;
+                    if let Ok(id) = &result {
+                        idempotency.record(&idempotency_key, request, id.to_str());
+                    }
+                    (result, false)
+                }
+                Err(error) => (Err(Error::from_api_error(error)), false),
             }
-            let public_key = public_key.unwrap().to_str();
-            creation_req.public_key = Some(public_key);
         }
-    }
-    let Ok(request) = body.request.try_into() else {
-        return Err(warp::reject::custom(
-            Error::InvalidParameters{error: "Invalid request".to_owned()},
-        ));
     };
-    let signature = match body.signature {
-        Some(signature) => {
-            let signature = signature.try_into();
-            if signature.is_err() {
-                return handle_data(signature);
-            } else {
-                signature.unwrap()
-            }
+    let id = match result {
+        Ok(id) => id,
+        Err(error) => return Err(warp::reject::custom(error)),
+    };
+    let reply = match parameters.wait {
+        None => handle_data(Ok(request_id_response(&id.to_str())))?,
+        Some(wait) => {
+            let request = wait_for_request(
+                submitter.node(),
+                &notifications,
+                id,
+                Duration::from_secs(wait),
+            )
+            .await;
+            request_state_reply(request)?
         }
-        None => Signature::new(&request, &keys, digest_derivator).expect("Error signing request"),
     };
-    match node
-        .external_request(Signed {
-            content: request,
-            signature,
-        })
+    if replayed {
+        Ok(Box::new(warp::reply::with_header(
+            reply,
+            "Idempotent-Replayed",
+            "true",
+        )))
+    } else {
+        Ok(reply)
+    }
+}
+
+/// State of the request, with status 202 if it is still being processed
+fn request_state_reply(
+    request: Result<TapleRequest, ApiError>,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    match request {
+        Ok(request) => {
+            let status = if matches!(request.state, RequestState::Processing) {
+                StatusCode::ACCEPTED
+            } else {
+                StatusCode::OK
+            };
+            Ok(Box::new(warp::reply::with_status(
+                warp::reply::json(&TapleRequestStateResponse::from(request)),
+                status,
+            )))
+        }
+        Err(error) => handle_data::<Value>(Err(error)),
+    }
+}
+
+fn request_id_response(request_id: &str) -> Value {
+    serde_json::json!({
+        "request_id": request_id,
+    })
+}
+
+/// Send a batch of event requests
+///
+/// Allows to send several event requests in a single call. They are submitted in order and
+/// the failure of one of them does not prevent the submission of the rest.
+/// Unsigned requests are signed with the identity selected in their body or the Signing-Identity header, or with the one of the node.
+/// Fact requests are checked against the schema of the subject, as single requests are.
+#[utoipa::path(
+    post,
+    path = "/event-requests/batch",
+    tag = "Requests",
+    operation_id = "createEventRequestBatch",
+    context_path = "/api",
+    request_body = [PostEventRequestBodyPreSignature],
+    params(
+        ("Signing-Identity" = Option<String>, Header, description = "Identity of the client that signs the requests of the batch that are not signed"),
+    ),
+    responses(
+        (status = 200, description = "Result of each request, in the order they were sent", body = [EventRequestResultResponse],
+        example = json!(
+            [
+                {
+                    "request_id": "J8618wGO7hH4wRuEeL0Ob5XNI9Q73BlCNlV8cWBORq78"
+                },
+                {
+                    "error": {
+                        "code": 400,
+                        "error": "Invalid request"
+                    }
+                }
+            ]
+        )),
+        (status = 400, description = "Bad Request"),
+        (status = 500, description = "Internal Server Error"),
+    )
+)]
+pub async fn post_event_request_batch_handler(
+    submitter: EventRequestSubmitter,
+    identity: Option<String>,
+    body: Vec<PostEventRequestBodyPreSignature>,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    if body.len() > MAX_BATCH_SIZE {
+        return Err(warp::reject::custom(Error::InvalidParameters {
+            error: format!(
+                "A batch can not contain more than {} requests",
+                MAX_BATCH_SIZE
+            ),
+        }));
+    }
+    let mut results = Vec::with_capacity(body.len());
+    // Requests are submitted one by one so those about the same subject keep their order
+    for mut request in body {
+        let result = match select_identity(identity.clone(), request.identity.take()) {
+            Ok(selected) => {
+                request.identity = selected;
+                submitter.submit(request).await
+            }
+            Err(error) => Err(error),
+        };
+        results.push(EventRequestResultResponse::from(result));
+    }
+    Ok(Box::new(warp::reply::json(&results)))
+}
+
+/// Dry run of an event request
+///
+/// Checks whether an event request would be accepted without submitting it: the identifiers, the subject and its governance,
+/// and the signature if one is supplied. Every problem found is reported.
+/// For Fact requests whose payload is a JSON patch, the state of the subject after the event is included.
+#[utoipa::path(
+    post,
+    path = "/event-requests/dry-run",
+    tag = "Requests",
+    operation_id = "dryRunEventRequest",
+    context_path = "/api",
+    request_body(content = PostEventRequestBodyPreSignature, content_type = "application/json", description = "Event Request type and signature"),
+    responses(
+        (status = 200, description = "Result of the checks", body = DryRunResponse,
+        example = json!(
+            {
+                "valid": false,
+                "problems": [
+                    {
+                        "field": "subject_id",
+                        "error": "The subject has reached its end of life"
+                    },
+                    {
+                        "field": "signature",
+                        "error": "Only the owner of the subject can sign this request"
+                    }
+                ],
+                "subject": null,
+                "governance_id": "J7BgD3dqZ8vO4WEH7-rpWIH-IhMqaSDnuA9cvCM93G8Q",
+                "resulting_state": null
+            }
+        )),
+        (status = 400, description = "Bad Request"),
+    )
+)]
+pub async fn post_event_request_dry_run_handler(
+    node: Api,
+    body: PostEventRequestBodyPreSignature,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    Ok(Box::new(warp::reply::json(
+        &dry_run::dry_run(&node, body).await,
+    )))
+}
+
+/// Send a Fact request from the desired state
+///
+/// Computes the JSON patch that takes the subject from its current state to the desired one, in the same way as the taple-patch tool,
+/// and sends it in a Fact request, signed with the identity selected in the body or the Signing-Identity header, or with the one of the node.
+/// The patch is sent as the data of the "Patch" method of the contract unless other method is given.
+/// The desired state is checked against the schema of the subject before sending the request.
+#[utoipa::path(
+    post,
+    path = "/event-requests/desired-state",
+    tag = "Requests",
+    operation_id = "createDesiredStateRequest",
+    context_path = "/api",
+    request_body(content = DesiredStateRequestBody, content_type = "application/json", description = "Subject and the state it must have"),
+    params(
+        ("Signing-Identity" = Option<String>, Header, description = "Identity of the client that signs the request"),
+    ),
+    responses(
+        (status = 200, description = "Request Created Successfully", body = DesiredStateResponse,
+        example = json!(
+            {
+                "request_id": "J8618wGO7hH4wRuEeL0Ob5XNI9Q73BlCNlV8cWBORq78",
+                "patch": [
+                    {
+                        "op": "replace",
+                        "path": "/temperatura",
+                        "value": 20
+                    }
+                ]
+            }
+        )),
+        (status = 400, description = "Bad Request, also returned with the list of violations when the desired state does not match the schema of the subject", body = ErrorResponse),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    )
+)]
+pub async fn post_desired_state_request_handler(
+    submitter: EventRequestSubmitter,
+    identity: Option<String>,
+    body: DesiredStateRequestBody,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let identity = select_identity(identity, body.identity).map_err(warp::reject::custom)?;
+    let method = body.method.as_deref().unwrap_or("Patch");
+    match submitter
+        .submit_desired_state(body.subject_id, body.state, method, identity)
         .await
     {
-        Ok(id) => handle_data(Ok(serde_json::json!({
-            "request_id": id.to_str(),
-        }))),
-        Err(error) => handle_data(Err::<Value, ApiError>(error)),
+        Ok((request_id, patch)) => handle_data(Ok(DesiredStateResponse {
+            request_id: request_id.to_str(),
+            patch,
+        })),
+        Err(error) => Err(warp::reject::custom(error)),
     }
 }
 
@@ -615,6 +1181,7 @@ pub async fn get_taple_request_handler(
     context_path = "/api",
     params(
         ("id" = String, Path, description = "Event Request's unique id"),
+        ("wait" = Option<u64>, Query, description = "Seconds, up to 60, to wait for the request to finish"),
     ),
     responses(
         (status = 200, description = "Request Data successfully retrieved", body = TapleRequestStateResponse,
@@ -627,6 +1194,7 @@ pub async fn get_taple_request_handler(
                 "success": true
             }
         )),
+        (status = 202, description = "The request is still being processed after waiting", body = TapleRequestStateResponse),
         (status = 400, description = "Bad Request"),
         (status = 404, description = "Not Found"),
         (status = 500, description = "Internal Server Error"),
@@ -635,17 +1203,24 @@ pub async fn get_taple_request_handler(
 pub async fn get_taple_request_state_handler(
     request_id: String,
     node: Api,
+    notifications: NotificationHub,
+    parameters: WaitQuery,
 ) -> Result<Box<dyn warp::Reply>, Rejection> {
-    let result = if let Ok(id) = DigestIdentifier::from_str(&request_id) {
-        node.get_request(id)
-            .await
-            .map(TapleRequestStateResponse::from)
-    } else {
-        Err(ApiError::InvalidParameters(
+    let Ok(id) = DigestIdentifier::from_str(&request_id) else {
+        return handle_data::<Value>(Err(ApiError::InvalidParameters(
             "ID specified is not a valid Digest Identifier".to_string(),
-        ))
+        )));
     };
-    handle_data(result)
+    match parameters.wait {
+        None => handle_data(
+            node.get_request(id)
+                .await
+                .map(TapleRequestStateResponse::from),
+        ),
+        Some(wait) => request_state_reply(
+            wait_for_request(&node, &notifications, id, Duration::from_secs(wait)).await,
+        ),
+    }
 }
 
 /// Get subjects
@@ -662,7 +1237,8 @@ pub async fn get_taple_request_state_handler(
         ("subject_type" = Option<String>, Query, description = "Type of subjects requested (possibilities: all, governances)"),
         ("governanceid" = Option<String>, Query, description = "Governance id of subjects requested"),
         ("from" = Option<String>, Query, description = "Identifier of the initial subject to be considered in pagination"),
-        ("quantity" = Option<isize>, Query, description = "Quantity of subjects requested")
+        ("quantity" = Option<isize>, Query, description = "Quantity of subjects requested"),
+        ("envelope" = Option<bool>, Query, description = "Wrap the entries in a page with the cursors of the adjacent pages, also sent in the Link header"),
     ),
     responses(
         (status = 200, description = "Subjects Data successfully retrieved", body = [SubjectDataResponse],
@@ -734,32 +1310,34 @@ pub async fn get_subjects_handler(
         },
         None => SubjectType::All,
     };
+    let page = PageRequest::new(
+        parameters.envelope,
+        parameters.from.is_some(),
+        parameters.quantity,
+    );
+    let from = parameters.from.clone();
     let data = match subject_type {
         SubjectType::All => {
             if let Some(data) = &parameters.governanceid {
                 match DigestIdentifier::from_str(data) {
                     Ok(id) => {
-                        node.get_subjects_by_governance(id, parameters.from, parameters.quantity)
+                        node.get_subjects_by_governance(id, from, page.quantity())
                             .await
                     }
                     Err(_) => Err(ApiError::InvalidParameters("governanceid".to_owned())),
                 }
             } else {
-                node.get_subjects("".into(), parameters.from, parameters.quantity)
-                    .await
+                node.get_subjects("".into(), from, page.quantity()).await
             }
         }
-        SubjectType::Governances => {
-            node.get_governances("".into(), parameters.from, parameters.quantity)
-                .await
-        }
+        SubjectType::Governances => node.get_governances("".into(), from, page.quantity()).await,
     }
     .map(|s| {
         s.into_iter()
             .map(SubjectDataResponse::from)
             .collect::<Vec<SubjectDataResponse>>()
     });
-    handle_data(data)
+    handle_page(data, page, &parameters)
 }
 
 /// Get subject by ID
@@ -824,6 +1402,338 @@ pub async fn get_subject_handler(id: String, node: Api) -> Result<Box<dyn warp::
     handle_data(response)
 }
 
+/// Get subject state at a sequence number
+///
+/// Rebuilds the state that a subject had after one of its events by replaying the patches of its events from the genesis one.
+/// The hash of the rebuilt state can be checked against the state hash recorded in the event.
+#[utoipa::path(
+    get,
+    path = "/subjects/{id}/state",
+    operation_id = "Get Subject State",
+    tag = "Subjects",
+    context_path = "/api",
+    params(
+        ("id" = String, Path, description = "Subject's unique id"),
+        ("sn" = Option<u64>, Query, description = "Sequence number of the event. The current one if absent"),
+    ),
+    responses(
+        (status = 200, description = "State of the subject after the event", body = SubjectStateResponse,
+        example = json!(
+            {
+                "subject_id": "JoifaSpfenD2bEPeBLvUTWh30brm4tKcvdW8exQnkGoQ",
+                "sn": 1,
+                "properties": {
+                    "localizacion": "Spain",
+                    "temperatura": 10
+                },
+                "state_hash": "JP3CyM4cH8a6MAwLsbXo9NjxnykJxLGbmdfVXB2vU_m4",
+                "event_state_hash": "JP3CyM4cH8a6MAwLsbXo9NjxnykJxLGbmdfVXB2vU_m4"
+            }
+        )),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    )
+)]
+pub async fn get_subject_state_handler(
+    id: String,
+    node: Api,
+    parameters: GetSubjectStateQuery,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let Ok(id) = DigestIdentifier::from_str(&id) else {
+        return handle_data::<Value>(Err(ApiError::InvalidParameters(
+            "ID specified is not a valid Digest Identifier".to_string(),
+        )));
+    };
+    let sn = match parameters.sn {
+        Some(sn) => sn,
+        None => match node.get_subject(id.clone()).await {
+            Ok(subject) => subject.sn,
+            Err(error) => return handle_data::<Value>(Err(error)),
+        },
+    };
+    match history::state_at(&node, id, sn).await {
+        Ok(state) => Ok(Box::new(warp::reply::json(&state))),
+        Err(error) => Err(warp::reject::custom(error)),
+    }
+}
+
+/// Verify subject chain
+///
+/// Walks the whole chain of events of a subject checking that sequence numbers are contiguous, that every event links to the previous one through its hash,
+/// that signatures are valid and made with the key of the subject, and that replaying the patches reproduces every state hash.
+/// The report names the first broken link. The same checks are available offline with the taple-verify tool.
+#[utoipa::path(
+    get,
+    path = "/subjects/{id}/verify",
+    operation_id = "Verify Subject",
+    tag = "Subjects",
+    context_path = "/api",
+    params(
+        ("id" = String, Path, description = "Subject's unique id"),
+    ),
+    responses(
+        (status = 200, description = "Report of the verification", body = VerificationReportResponse,
+        example = json!(
+            {
+                "subject_id": "JoifaSpfenD2bEPeBLvUTWh30brm4tKcvdW8exQnkGoQ",
+                "valid": false,
+                "verified_events": 3,
+                "total_events": 5,
+                "first_broken": {
+                    "sn": 3,
+                    "check": "state_hash",
+                    "error": "state_hash is JP3CyM4cH8a6MAwLsbXo9NjxnykJxLGbmdfVXB2vU_m4 but the replayed state hashes to J8618wGO7hH4wRuEeL0Ob5XNI9Q73BlCNlV8cWBORq78"
+                }
+            }
+        )),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    )
+)]
+pub async fn get_subject_verification_handler(
+    id: String,
+    node: Api,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let Ok(id) = DigestIdentifier::from_str(&id) else {
+        return handle_data::<Value>(Err(ApiError::InvalidParameters(
+            "ID specified is not a valid Digest Identifier".to_string(),
+        )));
+    };
+    match history::verify(&node, id).await {
+        Ok(report) => Ok(Box::new(warp::reply::json(
+            &VerificationReportResponse::from(report),
+        ))),
+        Err(error) => Err(warp::reject::custom(error)),
+    }
+}
+
+/// Get governance members
+///
+/// Allows to obtain the members of a governance.
+/// They can be filtered by the roles they hold, optionally restricted to the roles that apply to a schema.
+#[utoipa::path(
+    get,
+    path = "/governances/{id}/members",
+    operation_id = "Get Governance Members",
+    tag = "Governances",
+    context_path = "/api",
+    params(
+        ("id" = String, Path, description = "Governance's unique id"),
+        ("role" = Option<String>, Query, description = "Only members that hold this role"),
+        ("schema" = Option<String>, Query, description = "Only members whose roles apply to this schema"),
+    ),
+    responses(
+        (status = 200, description = "Members successfully retrieved", body = [GovernanceMemberResponse],
+        example = json!(
+            [
+                {
+                    "id": "EbwR0yYrCYpTzlN5i5GX_MtAbKRw5y2euv3TqiTgwggs",
+                    "name": "WPO"
+                },
+                {
+                    "id": "Et8MGMM6C9XzXSQ9S3JwtQaP7XjpIHDUnOIG1ZDFjSDU",
+                    "name": "Premium_Wines"
+                }
+            ]
+        )),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    )
+)]
+pub async fn get_governance_members_handler(
+    id: String,
+    node: Api,
+    parameters: GetGovernanceMembersQuery,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let members = match get_governance(&node, &id).await {
+        Ok(governance) => governance.members(&parameters),
+        Err(error) => Err(error),
+    };
+    match members {
+        Ok(members) => Ok(Box::new(warp::reply::json(&members))),
+        Err(error) => Err(warp::reject::custom(error)),
+    }
+}
+
+/// Get governance roles
+///
+/// Allows to obtain the roles defined by a governance.
+/// They can be filtered by the member that holds them, given by public key or name, by role and by the schema on which they apply.
+#[utoipa::path(
+    get,
+    path = "/governances/{id}/roles",
+    operation_id = "Get Governance Roles",
+    tag = "Governances",
+    context_path = "/api",
+    params(
+        ("id" = String, Path, description = "Governance's unique id"),
+        ("member" = Option<String>, Query, description = "Only roles held by this member, given by public key or name"),
+        ("role" = Option<String>, Query, description = "Only this role"),
+        ("schema" = Option<String>, Query, description = "Only roles that apply to this schema"),
+    ),
+    responses(
+        (status = 200, description = "Roles successfully retrieved", body = [GovernanceRoleResponse],
+        example = json!(
+            [
+                {
+                    "who": {
+                        "NAME": "WPO"
+                    },
+                    "namespace": "",
+                    "role": "VALIDATOR",
+                    "schema": "ALL"
+                },
+                {
+                    "who": "MEMBERS",
+                    "namespace": "",
+                    "role": "WITNESS",
+                    "schema": {
+                        "ID": "Wine"
+                    }
+                }
+            ]
+        )),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    )
+)]
+pub async fn get_governance_roles_handler(
+    id: String,
+    node: Api,
+    parameters: GetGovernanceRolesQuery,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let roles = match get_governance(&node, &id).await {
+        Ok(governance) => governance.roles(&parameters),
+        Err(error) => Err(error),
+    };
+    match roles {
+        Ok(roles) => Ok(Box::new(warp::reply::json(&roles))),
+        Err(error) => Err(warp::reject::custom(error)),
+    }
+}
+
+/// Get governance schemas
+///
+/// Allows to obtain the schemas defined by a governance, with the initial value and the contract of their subjects.
+#[utoipa::path(
+    get,
+    path = "/governances/{id}/schemas",
+    operation_id = "Get Governance Schemas",
+    tag = "Governances",
+    context_path = "/api",
+    params(
+        ("id" = String, Path, description = "Governance's unique id"),
+    ),
+    responses(
+        (status = 200, description = "Schemas successfully retrieved", body = [GovernanceSchemaResponse],
+        example = json!(
+            [
+                {
+                    "id": "Wine",
+                    "schema": {
+                        "type": "object",
+                        "properties": {
+                            "harvest": {
+                                "type": "integer"
+                            }
+                        },
+                        "required": ["harvest"],
+                        "additionalProperties": false
+                    },
+                    "initial_value": {
+                        "harvest": 0
+                    },
+                    "contract": {
+                        "raw": "dXNlIHRhcGxlX3NjX3J1c3QgYXMgc2RrOw=="
+                    }
+                }
+            ]
+        )),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    )
+)]
+pub async fn get_governance_schemas_handler(
+    id: String,
+    node: Api,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let schemas = match get_governance(&node, &id).await {
+        Ok(governance) => governance.schemas(),
+        Err(error) => Err(error),
+    };
+    match schemas {
+        Ok(schemas) => Ok(Box::new(warp::reply::json(&schemas))),
+        Err(error) => Err(warp::reject::custom(error)),
+    }
+}
+
+/// Get governance policies
+///
+/// Allows to obtain the quorums of approvers, evaluators and validators that a governance requires for each schema.
+#[utoipa::path(
+    get,
+    path = "/governances/{id}/policies",
+    operation_id = "Get Governance Policies",
+    tag = "Governances",
+    context_path = "/api",
+    params(
+        ("id" = String, Path, description = "Governance's unique id"),
+        ("schema" = Option<String>, Query, description = "Only the policy of this schema"),
+    ),
+    responses(
+        (status = 200, description = "Policies successfully retrieved", body = [GovernancePolicyResponse],
+        example = json!(
+            [
+                {
+                    "id": "governance",
+                    "approve": {
+                        "quorum": "MAJORITY"
+                    },
+                    "evaluate": {
+                        "quorum": "MAJORITY"
+                    },
+                    "validate": {
+                        "quorum": {
+                            "FIXED": 2
+                        }
+                    }
+                }
+            ]
+        )),
+        (status = 400, description = "Bad Request"),
+        (status = 404, description = "Not Found"),
+        (status = 500, description = "Internal Server Error"),
+    )
+)]
+pub async fn get_governance_policies_handler(
+    id: String,
+    node: Api,
+    parameters: GetGovernancePoliciesQuery,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let policies = match get_governance(&node, &id).await {
+        Ok(governance) => governance.policies(&parameters),
+        Err(error) => Err(error),
+    };
+    match policies {
+        Ok(policies) => Ok(Box::new(warp::reply::json(&policies))),
+        Err(error) => Err(warp::reject::custom(error)),
+    }
+}
+
+async fn get_governance(node: &Api, id: &str) -> Result<Governance, Error> {
+    let Ok(id) = DigestIdentifier::from_str(id) else {
+        return Err(Error::InvalidParameters {
+            error: "ID specified is not a valid Digest Identifier".to_string(),
+        });
+    };
+    Governance::get(node, id).await
+}
+
 /// Get validation proof
 ///
 /// Allows to obtain the validation test of the last event for a specified subject.
@@ -889,6 +1799,77 @@ pub async fn get_validation_proof_handle(
     handle_data(result)
 }
 
+/// Verify validation proof
+///
+/// Checks each signature of a validation proof against the proof and whether the validators that signed it reach the quorum.
+/// The validators and the quorum are the ones given in the request: they are not resolved from the governance,
+/// so the caller must take them from the governance of the subject for the proof's governance version.
+/// Quorums that require no signature, a fixed quorum of 0 or a percentage outside (0, 1], are rejected.
+/// The same checks are available offline with the taple-verify tool.
+#[utoipa::path(
+    post,
+    path = "/validation-proofs/verify",
+    operation_id = "Verify Validation Proof",
+    tag = "Subjects",
+    context_path = "/api",
+    request_body(content = VerifyProofBody, content_type = "application/json", description = "Proof, signatures and validators of the governance"),
+    responses(
+        (status = 200, description = "Report of the verification", body = ProofVerificationResponse,
+        example = json!(
+            {
+                "signatures": [
+                    {
+                        "signer": "EbwR0yYrCYpTzlN5i5GX_MtAbKRw5y2euv3TqiTgwggs",
+                        "valid": true,
+                        "validator": true
+                    },
+                    {
+                        "signer": "EF3E6fTSLrsEWzkD2tkB6QbJU9R7IOkunImqp0PB_ejg",
+                        "valid": false,
+                        "validator": true,
+                        "error": "Signature verification failed"
+                    }
+                ],
+                "valid_signatures": 1,
+                "required_signatures": 2,
+                "quorum_met": false
+            }
+        )),
+        (status = 400, description = "Bad Request, also returned for quorums that require no signature"),
+        (status = 500, description = "Internal Server Error"),
+    )
+)]
+pub async fn post_validation_proof_verification_handler(
+    body: VerifyProofBody,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    handle_data(verify_proof(body))
+}
+
+fn verify_proof(body: VerifyProofBody) -> Result<ProofVerificationResponse, ApiError> {
+    let proof: ValidationProof = body.proof.try_into()?;
+    let signatures = body
+        .signatures
+        .into_iter()
+        .map(TryInto::<Signature>::try_into)
+        .collect::<Result<Vec<_>, _>>()?;
+    let validators = body
+        .validators
+        .iter()
+        .map(|validator| {
+            KeyIdentifier::from_str(validator).map_err(|_| {
+                ApiError::InvalidParameters(format!(
+                    "Invalid KeyIdentifier for validator {}",
+                    validator
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let quorum = body.quorum.map(Quorum::from).unwrap_or_default();
+    taple_verification::verify_proof(&proof, &signatures, &validators, quorum)
+        .map(ProofVerificationResponse::from)
+        .map_err(|error| ApiError::InvalidParameters(error.to_string()))
+}
+
 /// Get events of a subject
 ///
 /// Allows to obtain, with pagination, the list of events of a subject.
@@ -902,6 +1883,7 @@ pub async fn get_validation_proof_handle(
         ("id" = String, Path, description = "Subject's unique id"),
         ("from" = Option<usize>, Query, description = "SN from which the event list should begin"),
         ("quantity" = Option<usize>, Query, description = "Quantity of events requested"),
+        ("envelope" = Option<bool>, Query, description = "Wrap the entries in a page with the cursors of the adjacent pages, also sent in the Link header"),
     ),
     responses(
         (status = 200, description = "Subjects Data successfully retrieved", body = [SignedEvent],
@@ -984,8 +1966,13 @@ pub async fn get_events_of_subject_handler(
     node: Api,
     parameters: GetWithPagination,
 ) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let page = PageRequest::new(
+        parameters.envelope,
+        parameters.from.is_some(),
+        parameters.quantity,
+    );
     let result = if let Ok(id) = DigestIdentifier::from_str(&id) {
-        node.get_events(id, parameters.from, parameters.quantity)
+        node.get_events(id, parameters.from, page.quantity())
             .await
             .map(|ve| {
                 ve.into_iter()
@@ -997,7 +1984,7 @@ pub async fn get_events_of_subject_handler(
             "ID specified is not a valid Digest Identifier".to_string(),
         ))
     };
-    handle_data::<Vec<SignedEvent>>(result)
+    handle_page::<SignedEvent, _>(result, page, &parameters)
 }
 
 /// Get an event from a subject
@@ -1103,35 +2090,272 @@ pub async fn get_event_handler(
     handle_data(response)
 }
 
+/// Subscribe to notifications (Server-Sent Events)
+///
+/// Opens a stream of Server-Sent Events with the notifications produced by the node.
+/// Each event is named after the kind of notification and carries it as JSON data.
+#[utoipa::path(
+    get,
+    path = "/notifications/sse",
+    operation_id = "Subscribe Notifications SSE",
+    tag = "Notifications",
+    context_path = "/api",
+    params(
+        ("subject_id" = Option<String>, Query, description = "Only notifications about this subject"),
+        ("governance_id" = Option<String>, Query, description = "Only notifications about subjects of this governance"),
+        ("kind" = Option<String>, Query, description = "Comma separated notification kinds (possibilities: new_subject, new_event, state_updated, approval_received, obsoleted_approval, unrecoverable_error)"),
+    ),
+    responses(
+        (status = 200, description = "Stream of notifications", body = NotificationResponse, content_type = "text/event-stream",
+        example = json!(
+            {
+                "kind": "new_event",
+                "subject_id": "JoifaSpfenD2bEPeBLvUTWh30brm4tKcvdW8exQnkGoQ",
+                "sn": 1
+            }
+        )),
+        (status = 400, description = "Bad Request"),
+    )
+)]
+pub async fn get_notifications_sse_handler(
+    node: Api,
+    notifications: NotificationHub,
+    parameters: GetNotificationsQuery,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let filter = match NotificationFilter::new(
+        parameters.subject_id,
+        parameters.governance_id,
+        parameters.kind,
+    ) {
+        Ok(filter) => filter,
+        Err(error) => return handle_data::<Value>(Err(error)),
+    };
+    let stream = filter
+        .stream(node, notifications.subscribe())
+        .map(|notification| {
+            warp::sse::Event::default()
+                .event(notification_kind(&notification))
+                .json_data(NotificationResponse::from(notification))
+        });
+    Ok(Box::new(warp::sse::reply(
+        warp::sse::keep_alive().stream(stream),
+    )))
+}
+
+/// Subscribe to notifications (WebSocket)
+///
+/// Upgrades the connection to a WebSocket through which the notifications produced by the node
+/// are sent as JSON text messages. Messages sent by the client are ignored.
+#[utoipa::path(
+    get,
+    path = "/notifications/ws",
+    operation_id = "Subscribe Notifications WebSocket",
+    tag = "Notifications",
+    context_path = "/api",
+    params(
+        ("subject_id" = Option<String>, Query, description = "Only notifications about this subject"),
+        ("governance_id" = Option<String>, Query, description = "Only notifications about subjects of this governance"),
+        ("kind" = Option<String>, Query, description = "Comma separated notification kinds (possibilities: new_subject, new_event, state_updated, approval_received, obsoleted_approval, unrecoverable_error)"),
+    ),
+    responses(
+        (status = 101, description = "Switching to the WebSocket protocol"),
+        (status = 400, description = "Bad Request"),
+    )
+)]
+pub async fn get_notifications_ws_handler(
+    ws: Ws,
+    node: Api,
+    notifications: NotificationHub,
+    parameters: GetNotificationsQuery,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let filter = match NotificationFilter::new(
+        parameters.subject_id,
+        parameters.governance_id,
+        parameters.kind,
+    ) {
+        Ok(filter) => filter,
+        Err(error) => return handle_data::<Value>(Err(error)),
+    };
+    let receiver = notifications.subscribe();
+    Ok(Box::new(ws.on_upgrade(move |socket| async move {
+        let (mut sender, mut incoming) = socket.split();
+        let mut stream = Box::pin(filter.stream(node, receiver));
+        loop {
+            tokio::select! {
+                notification = stream.next() => {
+                    let Some(notification) = notification else {
+                        break;
+                    };
+                    let Ok(text) = serde_json::to_string(&NotificationResponse::from(notification)) else {
+                        continue;
+                    };
+                    if sender.send(Message::text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                message = incoming.next() => match message {
+                    Some(Ok(message)) if !message.is_close() => {}
+                    _ => break,
+                }
+            }
+        }
+    })))
+}
+
+/// Get webhooks
+///
+/// Lists the webhooks that receive the notifications of the node, both the ones defined in the settings and the ones registered through the API.
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    operation_id = "Get Webhooks",
+    tag = "Webhooks",
+    context_path = "/api",
+    responses(
+        (status = 200, description = "Webhooks", body = [WebhookResponse],
+        example = json!(
+            [
+                {
+                    "id": "5f0a4a7f0e3c1b2d",
+                    "url": "https://example.com/taple",
+                    "configured": false
+                }
+            ]
+        )),
+    )
+)]
+pub async fn get_webhooks_handler(webhooks: Webhooks) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let webhooks: Vec<WebhookResponse> = webhooks.list().into_iter().map(Into::into).collect();
+    handle_data(Ok(webhooks))
+}
+
+/// Register webhook
+///
+/// Registers a URL to which a JSON POST is sent for each notification produced by the node.
+/// Deliveries are signed in the X-Taple-Signature header with the HMAC-SHA256 of "<X-Taple-Timestamp>.<body>".
+/// Failed deliveries are retried with exponential backoff and then kept as dead letters.
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    operation_id = "Register Webhook",
+    tag = "Webhooks",
+    context_path = "/api",
+    request_body(content = PostWebhookBody, content_type = "application/json", description = "URL of the webhook and its secret"),
+    responses(
+        (status = 200, description = "Webhook registered", body = WebhookResponse),
+        (status = 400, description = "Bad Request"),
+        (status = 409, description = "The URL is already registered"),
+    )
+)]
+pub async fn post_webhook_handler(
+    webhooks: Webhooks,
+    body: PostWebhookBody,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    match webhooks.register(body.url, body.secret) {
+        Ok(webhook) => handle_data(Ok(WebhookResponse::from(webhook))),
+        Err(error) => Err(warp::reject::custom(Error::from_webhook_error(error))),
+    }
+}
+
+/// Remove webhook
+///
+/// Removes a webhook registered through the API. Its pending deliveries are discarded.
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    operation_id = "Remove Webhook",
+    tag = "Webhooks",
+    context_path = "/api",
+    params(
+        ("id" = String, Path, description = "Webhook's unique id"),
+    ),
+    responses(
+        (status = 204, description = "Webhook removed"),
+        (status = 404, description = "Not Found"),
+        (status = 409, description = "The webhook is defined in the settings"),
+    )
+)]
+pub async fn delete_webhook_handler(
+    id: String,
+    webhooks: Webhooks,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    match webhooks.unregister(&id) {
+        Ok(()) => Ok(Box::new(StatusCode::NO_CONTENT)),
+        Err(error) => Err(warp::reject::custom(Error::from_webhook_error(error))),
+    }
+}
+
+/// Get webhook dead letters
+///
+/// Lists the deliveries that ran out of attempts.
+#[utoipa::path(
+    get,
+    path = "/webhooks/dead-letters",
+    operation_id = "Get Webhook Dead Letters",
+    tag = "Webhooks",
+    context_path = "/api",
+    responses(
+        (status = 200, description = "Failed deliveries", body = [WebhookDeliveryResponse],
+        example = json!(
+            [
+                {
+                    "id": "0000018b3f6c2a1000000004",
+                    "webhook_id": "5f0a4a7f0e3c1b2d",
+                    "notification": {
+                        "kind": "new_subject",
+                        "subject_id": "JKZgYhPjQdWNWWwkac0wSwqLKoOJsT0QimJmj6zjimWc"
+                    },
+                    "created_at": 1697627145,
+                    "attempts": 10,
+                    "last_error": "Webhook responded with status 503 Service Unavailable"
+                }
+            ]
+        )),
+    )
+)]
+pub async fn get_webhook_dead_letters_handler(
+    webhooks: Webhooks,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let dead_letters: Vec<WebhookDeliveryResponse> = webhooks
+        .dead_letters()
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    handle_data(Ok(dead_letters))
+}
+
+/// Redeliver dead letter
+///
+/// Queues again a failed delivery, with all its attempts available.
+#[utoipa::path(
+    post,
+    path = "/webhooks/dead-letters/{id}/redeliver",
+    operation_id = "Redeliver Webhook Dead Letter",
+    tag = "Webhooks",
+    context_path = "/api",
+    params(
+        ("id" = String, Path, description = "Delivery's unique id"),
+    ),
+    responses(
+        (status = 200, description = "Delivery queued", body = WebhookDeliveryResponse),
+        (status = 404, description = "Not Found"),
+    )
+)]
+pub async fn post_webhook_redelivery_handler(
+    id: String,
+    webhooks: Webhooks,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    match webhooks.redeliver(&id) {
+        Ok(delivery) => handle_data(Ok(WebhookDeliveryResponse::from(delivery))),
+        Err(error) => Err(warp::reject::custom(Error::from_webhook_error(error))),
+    }
+}
+
 pub fn handle_data<T: Serialize + std::fmt::Debug>(
     data: Result<T, ApiError>,
 ) -> Result<Box<dyn warp::Reply>, Rejection> {
-    match &data {
+    match data {
         Ok(data) => Ok(Box::new(warp::reply::json(&data))),
-        Err(ApiError::InvalidParameters(msg)) => {
-            Err(warp::reject::custom(Error::InvalidParameters {
-                error: msg.to_owned(),
-            }))
-        }
-        Err(ApiError::Conflict(msg)) => Err(warp::reject::custom(Error::Conflict {
-            error: msg.to_owned(),
-        })),
-        Err(ApiError::NotFound(msg)) => Err(warp::reject::custom(Error::NotFound {
-            error: msg.to_owned(),
-        })),
-        Err(ApiError::EventCreationError { .. }) => {
-            Err(warp::reject::custom(Error::ExecutionError {
-                source: data.unwrap_err(),
-            }))
-        }
-        Err(ApiError::NotEnoughPermissions(msg)) => {
-            Err(warp::reject::custom(Error::NotEnoughPermissions {
-                error: msg.to_owned(),
-            }))
-        }
-        // Err(ApiError::VoteNotNeeded(msg)) => Err(warp::reject::custom(Error::RequestError(msg.to_owned()))),
-        Err(error) => Err(warp::reject::custom(Error::ExecutionError {
-            source: error.to_owned(),
-        })),
+        Err(error) => Err(warp::reject::custom(Error::from_api_error(error))),
     }
 }
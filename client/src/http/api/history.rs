@@ -0,0 +1,89 @@
+use serde_json::json;
+use taple_core::{
+    identifier::{Derivable, DigestIdentifier},
+    signature::Signed,
+    Api, ApiError, Event,
+};
+use taple_verification::Report;
+
+use super::{error::Error, responses::SubjectStateResponse};
+use crate::utils::Batches;
+
+/// Events of a subject from the genesis one, up to `last_sn` if given
+async fn events(
+    node: &Api,
+    subject_id: &DigestIdentifier,
+    last_sn: Option<u64>,
+) -> Result<Vec<Signed<Event>>, Error> {
+    let mut events: Vec<Signed<Event>> = Vec::new();
+    let mut batches = Batches::new(Some(0), false);
+    while let Some(batch) = batches
+        .next(
+            |from, quantity| node.get_events(subject_id.clone(), from, Some(quantity)),
+            |event: &Signed<Event>| event.content.sn as i64 + 1,
+        )
+        .await
+        .map_err(Error::from_api_error)?
+    {
+        events.extend(batch);
+        if let Some(last_sn) = last_sn {
+            if events.len() as u64 > last_sn {
+                events.truncate(last_sn as usize + 1);
+                break;
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// Rebuilds the state of a subject at a sequence number by replaying the patches of
+/// its events from the genesis one
+pub async fn state_at(
+    node: &Api,
+    subject_id: DigestIdentifier,
+    sn: u64,
+) -> Result<SubjectStateResponse, Error> {
+    let events = events(node, &subject_id, Some(sn)).await?;
+    let mut state = json!({});
+    for (expected_sn, event) in events.iter().enumerate() {
+        if event.content.sn != expected_sn as u64 {
+            return Err(Error::InternalServerError {
+                error: format!(
+                    "Event {} of subject {} is missing",
+                    expected_sn,
+                    subject_id.to_str()
+                ),
+            });
+        }
+        taple_verification::apply_event(&mut state, &event.content)
+            .map_err(|error| Error::InternalServerError { error })?;
+    }
+    let Some(event) = events.last().filter(|event| event.content.sn == sn) else {
+        return Err(Error::from_api_error(ApiError::NotFound(format!(
+            "Event {} of subject {}",
+            sn,
+            subject_id.to_str()
+        ))));
+    };
+    let state_hash = taple_verification::state_hash(&state, &event.content.state_hash)
+        .map_err(|error| Error::InternalServerError { error })?;
+    Ok(SubjectStateResponse {
+        subject_id: subject_id.to_str(),
+        sn,
+        properties: state,
+        state_hash: state_hash.to_str(),
+        event_state_hash: event.content.state_hash.to_str(),
+    })
+}
+
+/// Checks the whole chain of events of a subject
+pub async fn verify(node: &Api, subject_id: DigestIdentifier) -> Result<Report, Error> {
+    let events = events(node, &subject_id, None).await?;
+    if events.is_empty() {
+        return Err(Error::from_api_error(ApiError::NotFound(format!(
+            "Events of subject {}",
+            subject_id.to_str()
+        ))));
+    }
+    Ok(taple_verification::verify(&events))
+}
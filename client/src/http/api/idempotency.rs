@@ -0,0 +1,192 @@
+use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use taple_core::{identifier::DigestIdentifier, ApiError, DatabaseCollection};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use tokio_util::sync::CancellationToken;
+
+use super::auth::Credential;
+use crate::{database::leveldb::LDBCollection, utils::unix_time};
+
+/// Header used by clients to identify retries of the same request
+pub const IDEMPOTENCY_HEADER: &str = "Idempotency-Key";
+
+const IDEMPOTENCY_PREFIX: &str = "idempotency/";
+const MAX_KEY_LENGTH: usize = 255;
+/// Maximum time between two purges of expired keys
+const MAX_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Response given to the first request sent with an idempotency key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IdempotencyRecord {
+    /// Body of the original request, to detect keys reused for a different request
+    request: String,
+    request_id: String,
+    /// Seconds since the Unix epoch
+    created_at: u64,
+}
+
+/// Outcome of looking up an idempotency key
+pub enum Lookup {
+    /// The key has not been used, or it has expired
+    New,
+    /// The key was already used for the same request, which was assigned this id
+    Replay(DigestIdentifier),
+}
+
+/// Idempotency key of a request. Keys are scoped to the credential that sent the request,
+/// so a caller can not replay nor collide with the keys of another one
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey {
+    credential: String,
+    key: String,
+}
+
+impl IdempotencyKey {
+    pub fn new(credential: &Credential, key: String) -> Self {
+        Self {
+            credential: credential.id(),
+            key,
+        }
+    }
+
+    fn storage_key(&self) -> String {
+        format!("{}{}/{}", IDEMPOTENCY_PREFIX, self.credential, self.key)
+    }
+}
+
+type KeyLocks = Arc<std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>>;
+
+/// Idempotency keys of the event requests received, stored in the database of the client
+#[derive(Clone)]
+pub struct IdempotencyStore {
+    db: Arc<LDBCollection>,
+    retention: Duration,
+    // Serializes the requests with the same key so two concurrent retries are not both submitted
+    locks: KeyLocks,
+}
+
+/// Lock of an idempotency key. The locks no request holds or waits for are removed on release
+pub struct KeyGuard {
+    locks: KeyLocks,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for KeyGuard {
+    fn drop(&mut self) {
+        // The guard keeps a reference to its lock, so it is released before counting them
+        self.guard.take();
+        self.locks
+            .lock()
+            .expect("Idempotency locks poisoned")
+            .retain(|_, lock| Arc::strong_count(lock) > 1);
+    }
+}
+
+impl IdempotencyStore {
+    pub fn new(db: Arc<LDBCollection>, retention: Duration) -> Self {
+        Self {
+            db,
+            retention,
+            locks: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Must be held from the lookup of a key until the response is recorded
+    pub async fn lock(&self, key: &IdempotencyKey) -> KeyGuard {
+        let lock = self
+            .locks
+            .lock()
+            .expect("Idempotency locks poisoned")
+            .entry(key.storage_key())
+            .or_default()
+            .clone();
+        KeyGuard {
+            locks: self.locks.clone(),
+            guard: Some(lock.lock_owned().await),
+        }
+    }
+
+    pub fn lookup(&self, key: &IdempotencyKey, request: &str) -> Result<Lookup, ApiError> {
+        validate_key(&key.key)?;
+        let Ok(data) = self.db.get(&key.storage_key()) else {
+            return Ok(Lookup::New);
+        };
+        let Ok(record) = serde_json::from_slice::<IdempotencyRecord>(&data) else {
+            return Ok(Lookup::New);
+        };
+        if self.is_expired(&record) {
+            return Ok(Lookup::New);
+        }
+        if record.request != request {
+            return Err(ApiError::Conflict(format!(
+                "{} {} was already used for a different request",
+                IDEMPOTENCY_HEADER, key.key
+            )));
+        }
+        match DigestIdentifier::from_str(&record.request_id) {
+            Ok(request_id) => Ok(Lookup::Replay(request_id)),
+            Err(_) => Ok(Lookup::New),
+        }
+    }
+
+    pub fn record(&self, key: &IdempotencyKey, request: String, request_id: String) {
+        let record = IdempotencyRecord {
+            request,
+            request_id,
+            created_at: unix_time().as_secs(),
+        };
+        let data = serde_json::to_vec(&record).expect("Serialize idempotency record");
+        if let Err(error) = self.db.put(&key.storage_key(), data) {
+            log::error!("Error storing idempotency key {}: {}", key.key, error);
+        }
+    }
+
+    fn is_expired(&self, record: &IdempotencyRecord) -> bool {
+        unix_time().as_secs().saturating_sub(record.created_at) > self.retention.as_secs()
+    }
+
+    /// Removes the expired keys
+    pub fn purge(&self) {
+        let expired: Vec<String> = self
+            .db
+            .iter(false, IDEMPOTENCY_PREFIX.to_owned())
+            .filter(|(_, data)| {
+                serde_json::from_slice::<IdempotencyRecord>(data)
+                    .map(|record| self.is_expired(&record))
+                    .unwrap_or(true)
+            })
+            .map(|(key, _)| key)
+            .collect();
+        for key in expired {
+            let _ = self.db.del(&format!("{}{}", IDEMPOTENCY_PREFIX, key));
+        }
+    }
+
+    /// Purges the expired keys periodically until the token is cancelled
+    pub fn spawn_purge(&self, cancellation_token: CancellationToken) {
+        let store = self.clone();
+        let period = self
+            .retention
+            .clamp(Duration::from_secs(1), MAX_PURGE_INTERVAL);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => break,
+                    _ = interval.tick() => store.purge(),
+                }
+            }
+        });
+    }
+}
+
+fn validate_key(key: &str) -> Result<(), ApiError> {
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH || !key.chars().all(|c| c.is_ascii_graphic()) {
+        return Err(ApiError::InvalidParameters(format!(
+            "{} must have between 1 and {} visible ASCII characters",
+            IDEMPOTENCY_HEADER, MAX_KEY_LENGTH
+        )));
+    }
+    Ok(())
+}
@@ -1,70 +1,167 @@
+pub mod approval_filter;
+pub mod auth;
 pub mod bodys;
+pub mod dry_run;
 pub mod error;
+pub mod governance;
 pub mod handlers;
+pub mod history;
+pub mod idempotency;
+pub mod pagination;
 pub mod querys;
 pub mod responses;
+pub mod schema;
+pub mod submission;
 
+use super::api::auth::{with_credential, with_scope, ApiKeys, Credential, Scope};
 use super::api::error::Error;
 use super::api::handlers::*;
+use super::api::idempotency::{IdempotencyKey, IdempotencyStore, IDEMPOTENCY_HEADER};
 use super::api::querys::*;
 use super::api::responses::ErrorResponse;
+use super::api::submission::{EventRequestSubmitter, IDENTITY_HEADER};
+use crate::approvals::ApprovalPolicy;
+use crate::http::Services;
+use crate::keys::KeyRegistry;
+use crate::notifications::NotificationHub;
+use crate::webhooks::Webhooks;
 use serde::de::DeserializeOwned;
-use taple_core::crypto::KeyPair;
-use taple_core::DigestDerivator;
-use taple_core::{Api, KeyDerivator};
+use taple_core::Api;
 use warp::body::BodyDeserializeError;
 use warp::{http::Response, hyper::StatusCode, Filter, Rejection, Reply};
 
 const API_BASE_PATH: &str = "api";
+/// Maximum size of the body of a batch of event requests
+const BATCH_BODY_LIMIT: u64 = 1024 * 1024 * 16;
 
 pub fn routes(
     taple_api: Api,
-    keys: KeyPair,
-    derivator: KeyDerivator,
-    digest_derivator: DigestDerivator,
+    submitter: EventRequestSubmitter,
+    api_keys: ApiKeys,
+    idempotency: IdempotencyStore,
+    services: Services,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     let root = warp::path(API_BASE_PATH);
+    let Services {
+        notifications,
+        webhooks,
+        approvals,
+        keys,
+        ..
+    } = services;
 
     root.and(
-        get_subject(taple_api.clone())
-            .or(get_all_subjects(taple_api.clone()))
-            .or(get_subject(taple_api.clone()))
+        get_subject(taple_api.clone(), api_keys.clone())
+            .or(get_all_subjects(taple_api.clone(), api_keys.clone()))
+            .or(get_subject(taple_api.clone(), api_keys.clone()))
+            .or(get_subject_state(taple_api.clone(), api_keys.clone()))
+            .or(get_subject_verification(
+                taple_api.clone(),
+                api_keys.clone(),
+            ))
             .or(post_event_request(
+                submitter.clone(),
+                idempotency,
+                notifications.clone(),
+                api_keys.clone(),
+            ))
+            .or(post_event_request_batch(
+                submitter.clone(),
+                api_keys.clone(),
+            ))
+            .or(post_desired_state_request(
+                submitter.clone(),
+                api_keys.clone(),
+            ))
+            .or(get_identities(submitter, api_keys.clone()))
+            .or(post_event_request_dry_run(
+                taple_api.clone(),
+                api_keys.clone(),
+            ))
+            .or(get_events_of_subject(taple_api.clone(), api_keys.clone()))
+            .or(get_governance_members(taple_api.clone(), api_keys.clone()))
+            .or(get_governance_roles(taple_api.clone(), api_keys.clone()))
+            .or(get_governance_schemas(taple_api.clone(), api_keys.clone()))
+            .or(get_governance_policies(taple_api.clone(), api_keys.clone()))
+            .or(get_event(taple_api.clone(), api_keys.clone()))
+            .or(patch_approval(taple_api.clone(), api_keys.clone()))
+            .or(patch_approvals(taple_api.clone(), api_keys.clone()))
+            .or(post_preauthorized_subjects(
+                taple_api.clone(),
+                api_keys.clone(),
+            ))
+            .or(get_preauthorized_subjects(
+                taple_api.clone(),
+                api_keys.clone(),
+            ))
+            .or(get_events_of_subject(taple_api.clone(), api_keys.clone()))
+            .or(get_validation_proof(taple_api.clone(), api_keys.clone()))
+            .or(post_validation_proof_verification(api_keys.clone()))
+            .or(post_generate_keys(
+                taple_api.clone(),
+                keys.clone(),
+                api_keys.clone(),
+            ))
+            .or(get_keys(keys.clone(), api_keys.clone()))
+            .or(get_key(keys.clone(), api_keys.clone()))
+            .or(delete_key(keys, api_keys.clone()))
+            .or(get_event_request(taple_api.clone(), api_keys.clone()))
+            .or(get_approval(taple_api.clone(), api_keys.clone()))
+            .or(get_approval_decision(approvals, api_keys.clone()))
+            .or(get_pending_approvals(taple_api.clone(), api_keys.clone()))
+            .or(get_notifications_sse(
+                taple_api.clone(),
+                notifications.clone(),
+                api_keys.clone(),
+            ))
+            .or(get_notifications_ws(
                 taple_api.clone(),
-                keys,
-                derivator,
-                digest_derivator,
+                notifications.clone(),
+                api_keys.clone(),
             ))
-            .or(get_events_of_subject(taple_api.clone()))
-            .or(get_event(taple_api.clone()))
-            .or(patch_approval(taple_api.clone()))
-            .or(post_preauthorized_subjects(taple_api.clone()))
-            .or(get_preauthorized_subjects(taple_api.clone()))
-            .or(get_events_of_subject(taple_api.clone()))
-            .or(get_validation_proof(taple_api.clone()))
-            .or(post_generate_keys(taple_api.clone()))
-            .or(get_event_request(taple_api.clone()))
-            .or(get_approval(taple_api.clone()))
-            .or(get_pending_approvals(taple_api.clone()))
-            .or(get_event_request_state(taple_api))
+            .or(get_event_request_state(
+                taple_api,
+                notifications,
+                api_keys.clone(),
+            ))
+            .or(get_webhooks(webhooks.clone(), api_keys.clone()))
+            .or(post_webhook(webhooks.clone(), api_keys.clone()))
+            .or(delete_webhook(webhooks.clone(), api_keys.clone()))
+            .or(get_webhook_dead_letters(webhooks.clone(), api_keys.clone()))
+            .or(post_webhook_redelivery(webhooks, api_keys))
             .recover(handle_rejection),
     )
 }
 
 pub fn get_approval(
     taple_api: Api,
+    api_keys: ApiKeys,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("approval-requests" / String)
         .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
         .and(with_taple_api(taple_api))
         .and_then(get_approval_handler)
 }
 
+pub fn get_approval_decision(
+    approvals: ApprovalPolicy,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("approval-requests" / String / "decision")
+        .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
+        .and(with_approvals(approvals))
+        .and_then(get_approval_decision_handler)
+}
+
 pub fn get_pending_approvals(
     taple_api: Api,
+    api_keys: ApiKeys,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("approval-requests")
         .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
         .and(with_taple_api(taple_api))
         .and(warp::query::<GetApprovalsQuery>())
         .and_then(get_approvals_handler)
@@ -72,80 +169,277 @@ pub fn get_pending_approvals(
 
 pub fn get_event_request(
     taple_api: Api,
+    api_keys: ApiKeys,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("event-requests" / String)
         .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
         .and(with_taple_api(taple_api))
         .and_then(get_taple_request_handler)
 }
 
+pub fn post_event_request_dry_run(
+    taple_api: Api,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("event-requests" / "dry-run")
+        .and(warp::post())
+        .and(with_scope(api_keys, Scope::Submit))
+        .and(with_taple_api(taple_api))
+        .and(with_body())
+        .and_then(post_event_request_dry_run_handler)
+}
+
+pub fn post_desired_state_request(
+    submitter: EventRequestSubmitter,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("event-requests" / "desired-state")
+        .and(warp::post())
+        .and(with_scope(api_keys, Scope::Submit))
+        .and(with_submitter(submitter))
+        .and(warp::header::optional::<String>(IDENTITY_HEADER))
+        .and(with_body())
+        .and_then(post_desired_state_request_handler)
+}
+
 pub fn get_event_request_state(
     taple_api: Api,
+    notifications: NotificationHub,
+    api_keys: ApiKeys,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("event-requests" / String / "state")
         .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
         .and(with_taple_api(taple_api))
+        .and(with_notifications(notifications))
+        .and(warp::query::<WaitQuery>())
         .and_then(get_taple_request_state_handler)
 }
 
-pub fn get_subject(taple_api: Api) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+pub fn get_webhooks(
+    webhooks: Webhooks,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("webhooks")
+        .and(warp::get())
+        .and(with_scope(api_keys, Scope::Admin))
+        .and(with_webhooks(webhooks))
+        .and_then(get_webhooks_handler)
+}
+
+pub fn post_webhook(
+    webhooks: Webhooks,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("webhooks")
+        .and(warp::post())
+        .and(with_scope(api_keys, Scope::Admin))
+        .and(with_webhooks(webhooks))
+        .and(with_body())
+        .and_then(post_webhook_handler)
+}
+
+pub fn delete_webhook(
+    webhooks: Webhooks,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("webhooks" / String)
+        .and(warp::delete())
+        .and(with_scope(api_keys, Scope::Admin))
+        .and(with_webhooks(webhooks))
+        .and_then(delete_webhook_handler)
+}
+
+pub fn get_webhook_dead_letters(
+    webhooks: Webhooks,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("webhooks" / "dead-letters")
+        .and(warp::get())
+        .and(with_scope(api_keys, Scope::Admin))
+        .and(with_webhooks(webhooks))
+        .and_then(get_webhook_dead_letters_handler)
+}
+
+pub fn post_webhook_redelivery(
+    webhooks: Webhooks,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("webhooks" / "dead-letters" / String / "redeliver")
+        .and(warp::post())
+        .and(with_scope(api_keys, Scope::Admin))
+        .and(with_webhooks(webhooks))
+        .and_then(post_webhook_redelivery_handler)
+}
+
+pub fn get_subject(
+    taple_api: Api,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("subjects" / String)
         .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
         .and(with_taple_api(taple_api))
         .and_then(get_subject_handler)
 }
 
+pub fn get_subject_state(
+    taple_api: Api,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("subjects" / String / "state")
+        .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
+        .and(with_taple_api(taple_api))
+        .and(warp::query::<GetSubjectStateQuery>())
+        .and_then(get_subject_state_handler)
+}
+
+pub fn get_subject_verification(
+    taple_api: Api,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("subjects" / String / "verify")
+        .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
+        .and(with_taple_api(taple_api))
+        .and_then(get_subject_verification_handler)
+}
+
 pub fn get_all_subjects(
     taple_api: Api,
+    api_keys: ApiKeys,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("subjects")
         .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
         .and(with_taple_api(taple_api))
         .and(warp::query::<GetAllSubjectsQuery>())
         .and_then(get_subjects_handler)
 }
 
 pub fn post_event_request(
-    taple_api: Api,
-    keys: KeyPair,
-    derivator: KeyDerivator,
-    digest_derivator: DigestDerivator,
+    submitter: EventRequestSubmitter,
+    idempotency: IdempotencyStore,
+    notifications: NotificationHub,
+    api_keys: ApiKeys,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("event-requests")
         .and(warp::post())
-        .and(with_taple_api(taple_api))
-        .and(with_keys(keys))
-        .and(with_derivator(derivator))
-        .and(with_digest_derivator(digest_derivator))
+        .and(with_idempotency_key(api_keys, Scope::Submit))
+        .and(with_submitter(submitter))
+        .and(with_idempotency(idempotency))
+        .and(with_notifications(notifications))
+        .and(warp::header::optional::<String>(IDENTITY_HEADER))
+        .and(warp::query::<WaitQuery>())
         .and(with_body())
         .and_then(post_event_request_handler)
 }
 
+pub fn post_event_request_batch(
+    submitter: EventRequestSubmitter,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("event-requests" / "batch")
+        .and(warp::post())
+        .and(with_scope(api_keys, Scope::Submit))
+        .and(with_submitter(submitter))
+        .and(warp::header::optional::<String>(IDENTITY_HEADER))
+        .and(with_body_limit(BATCH_BODY_LIMIT))
+        .and_then(post_event_request_batch_handler)
+}
+
 pub fn patch_approval(
     taple_api: Api,
+    api_keys: ApiKeys,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("approval-requests" / String)
         .and(warp::patch())
+        .and(with_scope(api_keys, Scope::Approve))
         .and(with_taple_api(taple_api))
         .and(with_body())
         .and_then(patch_approval_handler)
 }
 
+pub fn patch_approvals(
+    taple_api: Api,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("approval-requests")
+        .and(warp::patch())
+        .and(with_scope(api_keys, Scope::Approve))
+        .and(with_taple_api(taple_api))
+        .and(with_body())
+        .and_then(patch_approvals_handler)
+}
+
 pub fn post_generate_keys(
     taple_api: Api,
+    keys: KeyRegistry,
+    api_keys: ApiKeys,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("keys")
         .and(warp::post())
+        .and(with_scope(api_keys, Scope::Admin))
         .and(with_taple_api(taple_api))
+        .and(with_keys(keys))
         .and(warp::query::<AddKeysQuery>())
         .and_then(post_generate_keys_handler)
 }
 
+pub fn get_keys(
+    keys: KeyRegistry,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("keys")
+        .and(warp::get())
+        .and(with_scope(api_keys, Scope::Admin))
+        .and(with_keys(keys))
+        .and(warp::query::<GetKeysQuery>())
+        .and_then(get_keys_handler)
+}
+
+pub fn get_key(
+    keys: KeyRegistry,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("keys" / String)
+        .and(warp::get())
+        .and(with_scope(api_keys, Scope::Admin))
+        .and(with_keys(keys))
+        .and_then(get_key_handler)
+}
+
+pub fn delete_key(
+    keys: KeyRegistry,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("keys" / String)
+        .and(warp::delete())
+        .and(with_scope(api_keys, Scope::Admin))
+        .and(with_keys(keys))
+        .and_then(delete_key_handler)
+}
+
+pub fn get_identities(
+    submitter: EventRequestSubmitter,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("identities")
+        .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
+        .and(with_submitter(submitter))
+        .and_then(get_identities_handler)
+}
+
 pub fn post_preauthorized_subjects(
     taple_api: Api,
+    api_keys: ApiKeys,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("allowed-subjects" / String)
         .and(warp::put())
+        .and(with_scope(api_keys, Scope::Admin))
         .and(with_taple_api(taple_api))
         .and(with_body())
         .and_then(put_allowed_subjects_handler)
@@ -153,9 +447,11 @@ pub fn post_preauthorized_subjects(
 
 pub fn get_preauthorized_subjects(
     taple_api: Api,
+    api_keys: ApiKeys,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("allowed-subjects")
         .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
         .and(with_taple_api(taple_api))
         .and(warp::query::<GetWithPaginationString>())
         .and_then(get_allowed_subjects_handler)
@@ -163,74 +459,196 @@ pub fn get_preauthorized_subjects(
 
 pub fn get_events_of_subject(
     taple_api: Api,
+    api_keys: ApiKeys,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("subjects" / String / "events")
         .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
         .and(with_taple_api(taple_api))
         .and(warp::query::<GetWithPagination>())
         .and_then(get_events_of_subject_handler)
 }
 
-pub fn get_event(taple_api: Api) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+pub fn get_event(
+    taple_api: Api,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("subjects" / String / "events" / u64)
         .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
         .and(with_taple_api(taple_api))
         .and_then(get_event_handler)
 }
 
+pub fn get_governance_members(
+    taple_api: Api,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("governances" / String / "members")
+        .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
+        .and(with_taple_api(taple_api))
+        .and(warp::query::<GetGovernanceMembersQuery>())
+        .and_then(get_governance_members_handler)
+}
+
+pub fn get_governance_roles(
+    taple_api: Api,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("governances" / String / "roles")
+        .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
+        .and(with_taple_api(taple_api))
+        .and(warp::query::<GetGovernanceRolesQuery>())
+        .and_then(get_governance_roles_handler)
+}
+
+pub fn get_governance_schemas(
+    taple_api: Api,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("governances" / String / "schemas")
+        .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
+        .and(with_taple_api(taple_api))
+        .and_then(get_governance_schemas_handler)
+}
+
+pub fn get_governance_policies(
+    taple_api: Api,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("governances" / String / "policies")
+        .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
+        .and(with_taple_api(taple_api))
+        .and(warp::query::<GetGovernancePoliciesQuery>())
+        .and_then(get_governance_policies_handler)
+}
+
 pub fn get_validation_proof(
     taple_api: Api,
+    api_keys: ApiKeys,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("subjects" / String / "validation")
         .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
         .and(with_taple_api(taple_api))
         .and_then(get_validation_proof_handle)
 }
 
+pub fn post_validation_proof_verification(
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("validation-proofs" / "verify")
+        .and(warp::post())
+        .and(with_scope(api_keys, Scope::Read))
+        .and(with_body())
+        .and_then(post_validation_proof_verification_handler)
+}
+
+pub fn get_notifications_sse(
+    taple_api: Api,
+    notifications: NotificationHub,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("notifications" / "sse")
+        .and(warp::get())
+        .and(with_scope(api_keys, Scope::Read))
+        .and(with_taple_api(taple_api))
+        .and(with_notifications(notifications))
+        .and(warp::query::<GetNotificationsQuery>())
+        .and_then(get_notifications_sse_handler)
+}
+
+pub fn get_notifications_ws(
+    taple_api: Api,
+    notifications: NotificationHub,
+    api_keys: ApiKeys,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("notifications" / "ws")
+        .and(warp::ws())
+        .and(with_scope(api_keys, Scope::Read))
+        .and(with_taple_api(taple_api))
+        .and(with_notifications(notifications))
+        .and(warp::query::<GetNotificationsQuery>())
+        .and_then(get_notifications_ws_handler)
+}
+
 pub fn with_taple_api(
     taple_api: Api,
 ) -> impl Filter<Extract = (Api,), Error = std::convert::Infallible> + Clone {
     warp::any().map(move || taple_api.clone())
 }
 
+pub fn with_submitter(
+    submitter: EventRequestSubmitter,
+) -> impl Filter<Extract = (EventRequestSubmitter,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || submitter.clone())
+}
+
+pub fn with_webhooks(
+    webhooks: Webhooks,
+) -> impl Filter<Extract = (Webhooks,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || webhooks.clone())
+}
+
+pub fn with_approvals(
+    approvals: ApprovalPolicy,
+) -> impl Filter<Extract = (ApprovalPolicy,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || approvals.clone())
+}
+
 pub fn with_keys(
-    keys: KeyPair,
-) -> impl Filter<Extract = (KeyPair,), Error = std::convert::Infallible> + Clone {
+    keys: KeyRegistry,
+) -> impl Filter<Extract = (KeyRegistry,), Error = std::convert::Infallible> + Clone {
     warp::any().map(move || keys.clone())
 }
 
-pub fn with_derivator(
-    derivator: KeyDerivator,
-) -> impl Filter<Extract = (KeyDerivator,), Error = std::convert::Infallible> + Clone {
-    warp::any().map(move || derivator)
+pub fn with_notifications(
+    notifications: NotificationHub,
+) -> impl Filter<Extract = (NotificationHub,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || notifications.clone())
 }
 
-pub fn with_digest_derivator(
-    derivator: DigestDerivator,
-) -> impl Filter<Extract = (DigestDerivator,), Error = std::convert::Infallible> + Clone {
-    warp::any().map(move || derivator)
+pub fn with_idempotency(
+    idempotency: IdempotencyStore,
+) -> impl Filter<Extract = (IdempotencyStore,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || idempotency.clone())
+}
+
+/// Authorizes the request and extracts its idempotency key, scoped to the credential
+pub fn with_idempotency_key(
+    api_keys: ApiKeys,
+    scope: Scope,
+) -> impl Filter<Extract = (Option<IdempotencyKey>,), Error = Rejection> + Clone {
+    with_credential(api_keys, scope)
+        .and(warp::header::optional::<String>(IDEMPOTENCY_HEADER))
+        .map(|credential: Credential, key: Option<String>| {
+            key.map(|key| IdempotencyKey::new(&credential, key))
+        })
 }
 
 pub fn with_body<T: DeserializeOwned + Send>(
 ) -> impl Filter<Extract = (T,), Error = warp::Rejection> + Clone {
-    warp::body::content_length_limit(1024 * 100).and(warp::body::json())
+    with_body_limit(1024 * 100)
+}
+
+pub fn with_body_limit<T: DeserializeOwned + Send>(
+    limit: u64,
+) -> impl Filter<Extract = (T,), Error = warp::Rejection> + Clone {
+    warp::body::content_length_limit(limit).and(warp::body::json())
 }
 
 // TODO: refactor errors
 pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Rejection> {
+    let violations = err
+        .find::<Error>()
+        .map(Error::violations)
+        .unwrap_or_default();
     let (msg, status_code) = if let Some(ref err) = err.find::<Error>() {
-        match err {
-            Error::InternalServerError { error } => {
-                (error.to_owned(), StatusCode::INTERNAL_SERVER_ERROR)
-            }
-            Error::ExecutionError { source } => (source.to_string(), StatusCode::CONFLICT),
-            Error::InvalidParameters { error } => (error.to_string(), StatusCode::BAD_REQUEST),
-            Error::NotEnoughPermissions { error } => (error.to_string(), StatusCode::FORBIDDEN),
-            Error::NotFound { error } => (error.to_string(), StatusCode::NOT_FOUND),
-            Error::Unauthorized { error } => (error.to_string(), StatusCode::UNAUTHORIZED),
-            Error::BadRequest { error } => (error.to_string(), StatusCode::BAD_REQUEST),
-            Error::Conflict { error } => (error.to_string(), StatusCode::CONFLICT),
-        }
+        (err.message(), err.status_code())
     } else if err.is_not_found() {
         ("Not Found".to_owned(), StatusCode::NOT_FOUND)
     } else if let Some(ref err) = err.find::<BodyDeserializeError>() {
@@ -257,6 +675,7 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Rejection> {
     let error = ErrorResponse {
         code: status_code.as_u16(),
         error: msg,
+        violations,
     };
     let json_response = warp::reply::json(&error);
     Ok(Response::builder()
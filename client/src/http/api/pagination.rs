@@ -0,0 +1,188 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use taple_core::ApiError;
+use utoipa::ToSchema;
+use warp::{Rejection, Reply};
+
+use super::handlers::handle_data;
+use super::responses::{
+    ApprovalEntityResponse, PreauthorizedSubjectsResponse, SignedEvent, SubjectDataResponse,
+};
+
+/// Number of entries of a page when the query does not set `quantity`
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+
+/// Page of a list, with the cursors to request the adjacent ones
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[aliases(
+    SubjectsPage = Page<SubjectDataResponse>,
+    EventsPage = Page<SignedEvent>,
+    ApprovalsPage = Page<ApprovalEntityResponse>,
+    PreauthorizedSubjectsPage = Page<PreauthorizedSubjectsResponse>
+)]
+pub struct Page<T> {
+    /// Entries of the page
+    pub items: Vec<T>,
+    /// Value of `from` that returns the next page. Absent on the last page
+    pub next: Option<String>,
+    /// Value of `from` that, with a negative `quantity`, returns the previous page.
+    /// Absent on the first page
+    pub prev: Option<String>,
+}
+
+/// Entries of a list that can be used as cursors
+pub trait Paginated {
+    /// Value of `from` that returns the entries after this one
+    fn after(&self) -> Option<String>;
+    /// Value of `from` that, with a negative `quantity`, returns the entries before this one
+    fn before(&self) -> Option<String>;
+}
+
+impl Paginated for SubjectDataResponse {
+    fn after(&self) -> Option<String> {
+        Some(self.subject_id.clone())
+    }
+
+    fn before(&self) -> Option<String> {
+        Some(self.subject_id.clone())
+    }
+}
+
+impl Paginated for ApprovalEntityResponse {
+    fn after(&self) -> Option<String> {
+        Some(self.id.clone())
+    }
+
+    fn before(&self) -> Option<String> {
+        Some(self.id.clone())
+    }
+}
+
+impl Paginated for PreauthorizedSubjectsResponse {
+    fn after(&self) -> Option<String> {
+        Some(self.subject_id.clone())
+    }
+
+    fn before(&self) -> Option<String> {
+        Some(self.subject_id.clone())
+    }
+}
+
+// Unlike identifiers, sequence numbers are included by `from`
+impl Paginated for SignedEvent {
+    fn after(&self) -> Option<String> {
+        Some((self.0.content.sn + 1).to_string())
+    }
+
+    fn before(&self) -> Option<String> {
+        self.0.content.sn.checked_sub(1).map(|sn| sn.to_string())
+    }
+}
+
+/// Pagination requested by the query of a list endpoint
+#[derive(Debug, Clone, Copy)]
+pub struct PageRequest {
+    envelope: bool,
+    quantity: Option<i64>,
+    from_given: bool,
+}
+
+impl PageRequest {
+    pub fn new(envelope: Option<bool>, from_given: bool, quantity: Option<i64>) -> Self {
+        Self {
+            envelope: envelope.unwrap_or(false),
+            quantity,
+            from_given,
+        }
+    }
+
+    fn page_size(&self) -> i64 {
+        match self.quantity {
+            Some(quantity) if quantity != 0 => quantity,
+            _ => DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    /// Quantity to request to the node. With the envelope an extra entry is requested
+    /// to know if there are more pages
+    pub fn quantity(&self) -> Option<i64> {
+        if !self.envelope {
+            return self.quantity;
+        }
+        let size = self.page_size();
+        Some(size + size.signum())
+    }
+
+    fn page<T: Paginated>(&self, mut items: Vec<T>) -> Page<T> {
+        let size = self.page_size();
+        let limit = size.unsigned_abs() as usize;
+        let has_more = items.len() > limit;
+        items.truncate(limit);
+        let (further, back) = (
+            items.last().filter(|_| has_more),
+            items.first().filter(|_| self.from_given),
+        );
+        let (next, prev) = if size > 0 {
+            (further.and_then(T::after), back.and_then(T::before))
+        } else {
+            // Walking backwards, the entries come in reverse order
+            (back.and_then(T::after), further.and_then(T::before))
+        };
+        Page { items, next, prev }
+    }
+}
+
+/// Replies with the list, wrapped in a [`Page`] with `Link` headers if the envelope was requested
+pub fn handle_page<T, Q>(
+    data: Result<Vec<T>, ApiError>,
+    request: PageRequest,
+    query: &Q,
+) -> Result<Box<dyn Reply>, Rejection>
+where
+    T: Paginated + Serialize + std::fmt::Debug,
+    Q: Serialize,
+{
+    if !request.envelope {
+        return handle_data(data);
+    }
+    let page = match data {
+        Ok(items) => request.page(items),
+        Err(error) => return handle_data::<Vec<T>>(Err(error)),
+    };
+    let size = request.page_size().abs();
+    let links: Vec<String> = [("next", &page.next, size), ("prev", &page.prev, -size)]
+        .into_iter()
+        .filter_map(|(rel, cursor, quantity)| {
+            let cursor = cursor.as_ref()?;
+            Some(format!(
+                "<?{}>; rel=\"{}\"",
+                link_query(query, cursor, quantity)?,
+                rel
+            ))
+        })
+        .collect();
+    let reply = warp::reply::json(&page);
+    if links.is_empty() {
+        Ok(Box::new(reply))
+    } else {
+        Ok(Box::new(warp::reply::with_header(
+            reply,
+            "Link",
+            links.join(", "),
+        )))
+    }
+}
+
+/// Query string of the original request with the cursor and quantity replaced
+fn link_query<Q: Serialize>(query: &Q, from: &str, quantity: i64) -> Option<String> {
+    let Ok(Value::Object(query)) = serde_json::to_value(query) else {
+        return None;
+    };
+    let mut query: Map<String, Value> = query
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .collect();
+    query.insert("from".into(), Value::String(from.to_owned()));
+    query.insert("quantity".into(), Value::from(quantity));
+    serde_urlencoded::to_string(query).ok()
+}
@@ -1,7 +1,9 @@
-use serde::Deserialize;
-use taple_core::KeyDerivator;
-use utoipa::{IntoParams, ToSchema};
-#[derive(Debug, Clone, Deserialize, IntoParams)]
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+use crate::keys::KeyAlgorithms;
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
 #[into_params(parameter_in = Query)]
 pub struct GetAllSubjectsQuery {
     /// Subject from which the query is made (being excluded)
@@ -12,56 +14,116 @@ pub struct GetAllSubjectsQuery {
     pub subject_type: Option<String>,
     /// Governance identifier
     pub governanceid: Option<String>,
+    /// Wrap the entries in a page with the cursors of the adjacent pages
+    pub envelope: Option<bool>,
 }
 
-#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
 #[into_params(parameter_in = Query)]
 pub struct GetWithPagination {
     /// Event from which the query is made (being excluded)
     pub from: Option<i64>,
     /// Number of entries
     pub quantity: Option<i64>,
+    /// Wrap the entries in a page with the cursors of the adjacent pages
+    pub envelope: Option<bool>,
 }
 
-#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
 #[into_params(parameter_in = Query)]
 pub struct GetWithPaginationString {
     /// Subject from which the query is made (being excluded)
     pub from: Option<String>,
     /// Number of entries
     pub quantity: Option<i64>,
+    /// Wrap the entries in a page with the cursors of the adjacent pages
+    pub envelope: Option<bool>,
 }
 
 #[derive(Debug, Clone, Deserialize, IntoParams)]
 #[into_params(parameter_in = Query)]
-pub struct AddKeysQuery {
-    pub algorithm: Option<KeyAlgorithms>,
+pub struct GetKeysQuery {
+    /// Include the retired keys
+    pub retired: Option<bool>,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize, ToSchema)]
-pub enum KeyAlgorithms {
-    /// Ed25519 algorithm
-    Ed25519,
-    /// Secp256k1 algorithm
-    Secp256k1,
-}
-
-impl From<KeyAlgorithms> for KeyDerivator {
-    fn from(val: KeyAlgorithms) -> Self {
-        match val {
-            KeyAlgorithms::Ed25519 => KeyDerivator::Ed25519,
-            KeyAlgorithms::Secp256k1 => KeyDerivator::Secp256k1,
-        }
-    }
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AddKeysQuery {
+    pub algorithm: Option<KeyAlgorithms>,
 }
 
-#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
 #[into_params(parameter_in = Query)]
 pub struct GetApprovalsQuery {
     /// Status of approvals
     pub status: Option<String>,
+    /// Subject of the event request
+    pub subject_id: Option<String>,
+    /// Governance of the subject. Requests about the governance itself are included
+    pub governance_id: Option<String>,
+    /// Schema of the subject
+    pub schema_id: Option<String>,
+    /// Signer of the event request
+    pub requester: Option<String>,
+    /// Version of the governance with which the request was evaluated
+    pub gov_version: Option<u64>,
     /// Request for approval from which the query is made (being excluded)
     pub from: Option<String>,
     /// Number of entries
     pub quantity: Option<i64>,
+    /// Wrap the entries in a page with the cursors of the adjacent pages
+    pub envelope: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetNotificationsQuery {
+    /// Only notifications about this subject
+    pub subject_id: Option<String>,
+    /// Only notifications about subjects of this governance
+    pub governance_id: Option<String>,
+    /// Comma separated list of notification kinds
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetSubjectStateQuery {
+    /// Sequence number of the state. The current one if absent
+    pub sn: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WaitQuery {
+    /// Seconds to wait for the request to finish
+    pub wait: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetGovernanceMembersQuery {
+    /// Only members that hold this role
+    pub role: Option<String>,
+    /// Only members whose roles apply to this schema
+    pub schema: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetGovernanceRolesQuery {
+    /// Only roles held by this member, given by public key or name
+    pub member: Option<String>,
+    /// Only this role
+    pub role: Option<String>,
+    /// Only roles that apply to this schema
+    pub schema: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetGovernancePoliciesQuery {
+    /// Only the policy of this schema
+    pub schema: Option<String>,
 }
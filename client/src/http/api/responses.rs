@@ -1,15 +1,23 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 
+use crate::approvals::{Decision, Vote};
+use crate::health::Check;
 use crate::http::api::bodys::SignatureBody;
+use crate::http::api::error::Error;
+use crate::keys::{KeyAlgorithms, KeyRecord};
+use crate::webhooks::{Delivery, Webhook};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use taple_core::identifier::Derivable;
 use taple_core::request::{RequestState, TapleRequest};
-use taple_core::KeyIdentifier;
+use taple_core::{ApiError, KeyIdentifier};
 use taple_core::{
-    ApprovalEntity, ApprovalRequest, ApprovalResponse, ApprovalState, Event, SubjectData,
+    ApprovalEntity, ApprovalRequest, ApprovalResponse, ApprovalState, Event, Notification,
+    SubjectData,
 };
 use taple_core::{DigestIdentifier, ValidationProof};
+use taple_verification::{BrokenLink, ProofReport, Report, SignatureCheck};
 use utoipa::ToSchema;
 
 use super::bodys::SignedBody;
@@ -110,6 +118,64 @@ pub struct SubjectDataResponse {
     pub active: bool,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct SubjectStateResponse {
+    /// Subject identifier
+    pub subject_id: String,
+    /// Sequence number of the event after which the subject had this state
+    pub sn: u64,
+    /// State of the subject after the event
+    pub properties: Value,
+    /// Hash of the rebuilt state
+    pub state_hash: String,
+    /// Hash of the state recorded in the event. It must be equal to `state_hash`
+    pub event_state_hash: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct BrokenLinkResponse {
+    /// Sequence number of the first event that failed a check
+    pub sn: u64,
+    /// Check that failed (possibilities: sequence, subject, previous_event, signature, signer, patch, state_hash)
+    pub check: String,
+    pub error: String,
+}
+
+impl From<BrokenLink> for BrokenLinkResponse {
+    fn from(value: BrokenLink) -> Self {
+        Self {
+            sn: value.sn,
+            check: value.check.as_str().to_owned(),
+            error: value.error,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct VerificationReportResponse {
+    /// Subject identifier
+    pub subject_id: String,
+    /// Whether every event passed every check
+    pub valid: bool,
+    /// Number of events that passed every check
+    pub verified_events: u64,
+    pub total_events: u64,
+    /// First event of the chain that failed a check
+    pub first_broken: Option<BrokenLinkResponse>,
+}
+
+impl From<Report> for VerificationReportResponse {
+    fn from(value: Report) -> Self {
+        Self {
+            valid: value.is_valid(),
+            subject_id: value.subject_id.unwrap_or_default(),
+            verified_events: value.verified_events,
+            total_events: value.total_events,
+            first_broken: value.first_broken.map(BrokenLinkResponse::from),
+        }
+    }
+}
+
 impl From<SubjectData> for SubjectDataResponse {
     fn from(value: SubjectData) -> Self {
         Self {
@@ -364,7 +430,7 @@ impl From<ValidationProof> for ValidationProofResponse {
             name: value.name,
             subject_public_key: value.subject_public_key.to_str(),
             governance_id: value.governance_id.to_str(),
-            genesis_governance_version: value.governance_version,
+            genesis_governance_version: value.genesis_governance_version,
             sn: value.sn,
             prev_event_hash: value.prev_event_hash.to_str(),
             event_hash: value.event_hash.to_str(),
@@ -373,6 +439,33 @@ impl From<ValidationProof> for ValidationProofResponse {
     }
 }
 
+impl TryInto<ValidationProof> for ValidationProofResponse {
+    type Error = ApiError;
+
+    fn try_into(self) -> Result<ValidationProof, Self::Error> {
+        let digest = |id: &str, field: &str| {
+            DigestIdentifier::from_str(id).map_err(|_| {
+                ApiError::InvalidParameters(format!("Invalid DigestIdentifier for {}", field))
+            })
+        };
+        Ok(ValidationProof {
+            subject_id: digest(&self.subject_id, "subject id")?,
+            schema_id: self.schema_id,
+            namespace: self.namespace,
+            name: self.name,
+            subject_public_key: KeyIdentifier::from_str(&self.subject_public_key).map_err(
+                |_| ApiError::InvalidParameters("Invalid KeyIdentifier for public key".to_string()),
+            )?,
+            governance_id: digest(&self.governance_id, "governance id")?,
+            genesis_governance_version: self.genesis_governance_version,
+            sn: self.sn,
+            prev_event_hash: digest(&self.prev_event_hash, "previous event hash")?,
+            event_hash: digest(&self.event_hash, "event hash")?,
+            governance_version: self.governance_version,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct GetProofResponse {
     /// Current validation proof
@@ -381,12 +474,58 @@ pub struct GetProofResponse {
     pub signatures: Vec<SignatureBody>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SignatureCheckResponse {
+    /// Signer of the signature
+    pub signer: String,
+    /// Whether the signature is valid for the proof
+    pub valid: bool,
+    /// Whether the signer is one of the validators
+    pub validator: bool,
+    /// Reason why the signature is not valid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<SignatureCheck> for SignatureCheckResponse {
+    fn from(value: SignatureCheck) -> Self {
+        Self {
+            signer: value.signer,
+            valid: value.valid,
+            validator: value.validator,
+            error: value.error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProofVerificationResponse {
+    /// Result of each signature, in the order they were sent
+    pub signatures: Vec<SignatureCheckResponse>,
+    /// Distinct validators with a valid signature
+    pub valid_signatures: u32,
+    /// Signatures required by the quorum
+    pub required_signatures: u32,
+    pub quorum_met: bool,
+}
+
+impl From<ProofReport> for ProofVerificationResponse {
+    fn from(value: ProofReport) -> Self {
+        Self {
+            signatures: value.signatures.into_iter().map(Into::into).collect(),
+            valid_signatures: value.valid_signatures,
+            required_signatures: value.required_signatures,
+            quorum_met: value.quorum_met,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PreauthorizedSubjectsResponse {
     /// Subject identifier
-    subject_id: String, // DigestIdentifier
+    pub subject_id: String, // DigestIdentifier
     /// Providers acting on a specific subject
-    providers: Vec<String>,
+    pub providers: Vec<String>,
 }
 
 impl From<(DigestIdentifier, HashSet<KeyIdentifier>)> for PreauthorizedSubjectsResponse {
@@ -404,4 +543,435 @@ pub struct ErrorResponse {
     pub code: u16,
     /// Error message
     pub error: String,
+    /// Values that do not match the schema of the subject
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<SchemaViolationResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SchemaViolationResponse {
+    /// JSON Pointer to the value of the state that violates the schema
+    pub path: String,
+    /// Description of the violation
+    pub error: String,
+}
+
+/// Outcome of one of the event requests of a batch
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EventRequestResultResponse {
+    /// Identifier of the request, if it was accepted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Reason why the request was rejected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorResponse>,
+}
+
+/// Outcome of the vote of one of the requests for approval of a bulk vote
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApprovalVoteResultResponse {
+    /// Identifier of the request for approval
+    pub id: String,
+    /// State of the request after the vote, if it was cast
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<ApprovalStateResponse>,
+    /// Reason why the vote was not cast
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DryRunProblem {
+    /// Field of the request with the problem
+    pub field: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DryRunResponse {
+    /// Whether the request would be accepted
+    pub valid: bool,
+    /// Every problem found in the request
+    pub problems: Vec<DryRunProblem>,
+    /// Subject of the request, if it already exists
+    pub subject: Option<SubjectDataResponse>,
+    /// Governance of the subject
+    pub governance_id: Option<String>,
+    /// State of the subject after a Fact request whose payload is a JSON patch
+    pub resulting_state: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DesiredStateResponse {
+    /// Identifier of the Fact request
+    pub request_id: String,
+    /// JSON patch sent in the payload of the request
+    pub patch: Value,
+}
+
+impl From<Result<DigestIdentifier, Error>> for EventRequestResultResponse {
+    fn from(value: Result<DigestIdentifier, Error>) -> Self {
+        match value {
+            Ok(id) => Self {
+                request_id: Some(id.to_str()),
+                error: None,
+            },
+            Err(error) => Self {
+                request_id: None,
+                error: Some(ErrorResponse {
+                    code: error.status_code().as_u16(),
+                    error: error.message(),
+                    violations: error.violations(),
+                }),
+            },
+        }
+    }
+}
+
+impl From<(String, Result<ApprovalEntity, ApiError>)> for ApprovalVoteResultResponse {
+    fn from((id, result): (String, Result<ApprovalEntity, ApiError>)) -> Self {
+        match result {
+            Ok(approval) => Self {
+                id,
+                state: Some(approval.state.into()),
+                error: None,
+            },
+            Err(error) => {
+                let error = Error::from_api_error(error);
+                Self {
+                    id,
+                    state: None,
+                    error: Some(ErrorResponse {
+                        code: error.status_code().as_u16(),
+                        error: error.message(),
+                        violations: error.violations(),
+                    }),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NotificationResponse {
+    /// A new subject has been created
+    NewSubject { subject_id: String },
+    /// A new event has been generated
+    NewEvent { subject_id: String, sn: u64 },
+    /// A subject has been synchronized
+    StateUpdated { subject_id: String, sn: u64 },
+    /// A request for approval has been received
+    ApprovalReceived {
+        id: String,
+        subject_id: String,
+        sn: u64,
+    },
+    /// A request for approval has become obsolete
+    ObsoletedApproval {
+        id: String,
+        subject_id: String,
+        sn: u64,
+    },
+    /// The node has found an unrecoverable error
+    UnrecoverableError { error: String },
+}
+
+impl From<Notification> for NotificationResponse {
+    fn from(value: Notification) -> Self {
+        match value {
+            Notification::NewSubject { subject_id } => Self::NewSubject { subject_id },
+            Notification::NewEvent { sn, subject_id } => Self::NewEvent { subject_id, sn },
+            Notification::StateUpdated { sn, subject_id } => Self::StateUpdated { subject_id, sn },
+            Notification::ApprovalReceived { id, subject_id, sn } => {
+                Self::ApprovalReceived { id, subject_id, sn }
+            }
+            Notification::ObsoletedApproval { id, subject_id, sn } => {
+                Self::ObsoletedApproval { id, subject_id, sn }
+            }
+            Notification::UnrecoverableError { error } => Self::UnrecoverableError { error },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HealthResponse {
+    /// "up" while the process is running
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CheckResponse {
+    /// "up" or "down"
+    pub status: String,
+    /// Reason of the failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<Check> for CheckResponse {
+    fn from(value: Check) -> Self {
+        match value.result {
+            Ok(()) => Self {
+                status: "up".into(),
+                error: None,
+            },
+            Err(error) => Self {
+                status: "down".into(),
+                error: Some(error),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReadinessResponse {
+    /// "up" when every check succeeds, "down" otherwise
+    pub status: String,
+    /// Status of each dependency of the node: database, node and network
+    pub checks: HashMap<String, CheckResponse>,
+}
+
+impl From<Vec<Check>> for ReadinessResponse {
+    fn from(value: Vec<Check>) -> Self {
+        let ready = value.iter().all(|check| check.result.is_ok());
+        Self {
+            status: if ready { "up" } else { "down" }.into(),
+            checks: value
+                .into_iter()
+                .map(|check| (check.name.to_owned(), CheckResponse::from(check)))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookResponse {
+    pub id: String,
+    pub url: String,
+    /// Whether the webhook is defined in the settings. Those can not be removed through the API
+    pub configured: bool,
+}
+
+impl From<Webhook> for WebhookResponse {
+    fn from(value: Webhook) -> Self {
+        Self {
+            id: value.id,
+            url: value.url,
+            configured: value.configured,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDeliveryResponse {
+    pub id: String,
+    pub webhook_id: String,
+    pub notification: NotificationResponse,
+    /// Seconds since the Unix epoch at which the notification was queued
+    pub created_at: u64,
+    /// Attempts made since the delivery was queued or redelivered
+    pub attempts: u32,
+    /// Error of the last attempt
+    pub last_error: Option<String>,
+}
+
+impl From<Delivery> for WebhookDeliveryResponse {
+    fn from(value: Delivery) -> Self {
+        Self {
+            id: value.id,
+            webhook_id: value.webhook_id,
+            notification: value.notification,
+            created_at: value.created_at,
+            attempts: value.attempts,
+            last_error: value.last_error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GovernanceMemberResponse {
+    /// Public key of the member
+    pub id: String,
+    /// Name of the member, unique in the governance
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RoleWhoResponse {
+    /// The member with this public key
+    Id(String),
+    /// The member with this name
+    Name(String),
+    /// Every member of the governance
+    Members,
+    /// Anyone, member or not
+    All,
+    /// Anyone who is not a member
+    NotMembers,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RoleSchemaResponse {
+    /// A single schema
+    Id(String),
+    /// Every schema but the governance one
+    NotGovernance,
+    /// Every schema
+    All,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GovernanceRoleResponse {
+    /// Who holds the role
+    pub who: RoleWhoResponse,
+    /// Namespace of the subjects on which the role applies. Empty for every namespace
+    pub namespace: String,
+    /// Role held (VALIDATOR, CREATOR, ISSUER, WITNESS, APPROVER or EVALUATOR)
+    pub role: String,
+    /// Schemas of the subjects on which the role applies
+    pub schema: RoleSchemaResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GovernanceContractResponse {
+    /// Source code of the contract, encoded in base64
+    pub raw: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GovernanceSchemaResponse {
+    /// Schema identifier
+    pub id: String,
+    /// JSON Schema of the properties of the subjects
+    pub schema: Value,
+    /// Properties of the subjects when they are created
+    pub initial_value: Value,
+    /// Contract that evaluates the facts of the subjects
+    pub contract: GovernanceContractResponse,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PolicyQuorumResponse {
+    /// More than half of the signers
+    Majority,
+    /// A fixed number of signers
+    Fixed(u64),
+    /// A fraction, between 0 and 1, of the signers
+    Percentage(f64),
+    /// Byzantine fault tolerant quorum for the given fraction of faulty signers
+    Bft(f64),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PolicyStageResponse {
+    /// Signatures required in the stage
+    pub quorum: PolicyQuorumResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GovernancePolicyResponse {
+    /// Schema to which the policy applies
+    pub id: String,
+    /// Quorum of approvers
+    pub approve: PolicyStageResponse,
+    /// Quorum of evaluators
+    pub evaluate: PolicyStageResponse,
+    /// Quorum of validators
+    pub validate: PolicyStageResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApprovalDecisionResponse {
+    /// Identifier of the request for approval
+    pub approval_id: String,
+    pub subject_id: String,
+    pub sn: u64,
+    /// Name of the rule of the approval policy that matched the request
+    pub rule: String,
+    /// Whether the request was accepted
+    pub approved: bool,
+    /// Seconds since the Unix epoch at which the vote was cast
+    pub decided_at: u64,
+    /// Error returned by the node when casting the vote
+    pub error: Option<String>,
+}
+
+impl From<Decision> for ApprovalDecisionResponse {
+    fn from(value: Decision) -> Self {
+        Self {
+            approval_id: value.approval_id,
+            subject_id: value.subject_id,
+            sn: value.sn,
+            rule: value.rule,
+            approved: value.vote == Vote::Accept,
+            decided_at: value.decided_at,
+            error: value.error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct KeyResponse {
+    pub public_key: String,
+    /// Algorithm of the key pair
+    pub derivator: KeyAlgorithms,
+    /// Seconds since the Unix epoch at which the key pair was generated, or found if backfilled
+    pub created_at: u64,
+    /// Whether the key was found on a subject of the node when the client started instead of
+    /// being recorded when generated
+    pub backfilled: bool,
+    /// Seconds since the Unix epoch at which the key was retired
+    pub retired_at: Option<u64>,
+}
+
+impl From<KeyRecord> for KeyResponse {
+    fn from(value: KeyRecord) -> Self {
+        Self {
+            public_key: value.public_key,
+            derivator: value.derivator,
+            created_at: value.created_at,
+            backfilled: value.backfilled,
+            retired_at: value.retired_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct KeySubjectResponse {
+    pub subject_id: String,
+    pub schema_id: String,
+    pub name: String,
+    /// Whether the subject has not reached its end of life
+    pub active: bool,
+}
+
+impl From<SubjectData> for KeySubjectResponse {
+    fn from(value: SubjectData) -> Self {
+        Self {
+            subject_id: value.subject_id.to_str(),
+            schema_id: value.schema_id,
+            name: value.name,
+            active: value.active,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IdentityResponse {
+    /// Name used to select the identity
+    pub name: String,
+    /// Controller id of the identity. Unknown for external signers
+    pub controller_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct KeyDetailsResponse {
+    #[serde(flatten)]
+    pub key: KeyResponse,
+    /// Subjects whose public key is this one
+    pub subjects: Vec<KeySubjectResponse>,
 }
@@ -0,0 +1,83 @@
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::str::FromStr;
+use taple_core::{identifier::DigestIdentifier, Api, SubjectData};
+
+use super::{
+    bodys::FactRequestBody,
+    error::Error,
+    governance::{Governance, GOVERNANCE_SCHEMA},
+    responses::SchemaViolationResponse,
+};
+
+/// Patch of a payload in the `{"<method>": {"data": <patch>}}` form. The effect of other
+/// payloads depends on the evaluation of the contract
+pub fn payload_patch(payload: &Value) -> Option<json_patch::Patch> {
+    let data = payload
+        .as_object()
+        .filter(|payload| payload.len() == 1)
+        .and_then(|payload| payload.values().next())
+        .and_then(|method| method.get("data"))?;
+    serde_json::from_value(data.clone()).ok()
+}
+
+/// Checks the state a Fact request would lead to against the JSON Schema that the governance
+/// declares for the subject, so the request is rejected before being evaluated.
+/// Requests whose resulting state cannot be known beforehand are left to the node
+pub async fn validate_fact(node: &Api, request: &FactRequestBody) -> Result<(), Error> {
+    let Ok(id) = DigestIdentifier::from_str(&request.subject_id) else {
+        return Ok(());
+    };
+    let Some(patch) = payload_patch(&request.payload) else {
+        return Ok(());
+    };
+    let Ok(subject) = node.get_subject(id).await else {
+        return Ok(());
+    };
+    let mut state = subject.properties.0.clone();
+    json_patch::patch(&mut state, &patch).map_err(|error| Error::InvalidParameters {
+        error: format!("The patch of the payload can not be applied: {}", error),
+    })?;
+    let violations = schema_violations(node, &subject, &state).await;
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::SchemaViolations { violations })
+    }
+}
+
+/// Violations of the JSON Schema of a subject by one of its possible states. The schema of
+/// governances is checked by the node itself
+pub async fn schema_violations(
+    node: &Api,
+    subject: &SubjectData,
+    state: &Value,
+) -> Vec<SchemaViolationResponse> {
+    if subject.schema_id == GOVERNANCE_SCHEMA {
+        return Vec::new();
+    }
+    let schemas = match Governance::get(node, subject.governance_id.clone()).await {
+        Ok(governance) => governance.schemas(),
+        Err(error) => Err(error),
+    };
+    let Some(schema) = schemas.ok().and_then(|schemas| {
+        schemas
+            .into_iter()
+            .find(|schema| schema.id == subject.schema_id)
+    }) else {
+        return Vec::new();
+    };
+    let Ok(schema) = JSONSchema::compile(&schema.schema) else {
+        return Vec::new();
+    };
+    let violations = match schema.validate(state) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .map(|error| SchemaViolationResponse {
+                path: error.instance_path.to_string(),
+                error: error.to_string(),
+            })
+            .collect(),
+    };
+    violations
+}
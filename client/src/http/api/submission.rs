@@ -0,0 +1,204 @@
+use std::{str::FromStr, time::Duration};
+
+use serde_json::{json, Value};
+use taple_core::{
+    identifier::{Derivable, DigestIdentifier},
+    request::{RequestState, TapleRequest},
+    signature::Signed,
+    Api, ApiError, DigestDerivator, KeyDerivator,
+};
+use tokio::{
+    sync::broadcast::error::RecvError,
+    time::{timeout_at, Instant},
+};
+
+use super::{
+    bodys::{self, FactRequestBody, PostEventRequestBodyPreSignature},
+    error::Error,
+    schema,
+};
+use crate::{keys::KeyRegistry, notifications::NotificationHub, signer::Signers};
+
+/// Header that selects the identity that signs an event request
+pub const IDENTITY_HEADER: &str = "Signing-Identity";
+
+/// Identity selected in the body or, when the body selects none, in the [`IDENTITY_HEADER`]
+pub fn select_identity(
+    header: Option<String>,
+    body: Option<String>,
+) -> Result<Option<String>, Error> {
+    match (header, body) {
+        (Some(header), Some(body)) if header != body => Err(Error::InvalidParameters {
+            error: "The identities of the header and the body do not match".to_owned(),
+        }),
+        (header, body) => Ok(body.or(header)),
+    }
+}
+
+/// Longest time a request can be held waiting for an event request to finish
+pub const MAX_WAIT: Duration = Duration::from_secs(60);
+/// Time between checks of the state of a request when no notification arrives.
+/// Failed requests are not notified by the node.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Sends event requests to the node, signing them with the selected identity when they are not signed
+#[derive(Clone)]
+pub struct EventRequestSubmitter {
+    node: Api,
+    signers: Signers,
+    key_registry: KeyRegistry,
+    derivator: KeyDerivator,
+    digest_derivator: DigestDerivator,
+}
+
+impl EventRequestSubmitter {
+    pub fn new(
+        node: Api,
+        signers: Signers,
+        key_registry: KeyRegistry,
+        derivator: KeyDerivator,
+        digest_derivator: DigestDerivator,
+    ) -> Self {
+        Self {
+            node,
+            signers,
+            key_registry,
+            derivator,
+            digest_derivator,
+        }
+    }
+
+    pub fn node(&self) -> &Api {
+        &self.node
+    }
+
+    pub fn signers(&self) -> &Signers {
+        &self.signers
+    }
+
+    /// Submits the request. Fact requests are first checked against the schema of the subject
+    pub async fn submit(
+        &self,
+        mut body: PostEventRequestBodyPreSignature,
+    ) -> Result<DigestIdentifier, Error> {
+        if body.identity.is_some() && body.signature.is_some() {
+            return Err(Error::InvalidParameters {
+                error: "The identity can only be selected for requests without signature"
+                    .to_owned(),
+            });
+        }
+        if let bodys::EventRequestBody::Fact(request) = &body.request {
+            schema::validate_fact(&self.node, request).await?;
+        }
+        let signer = self
+            .signers
+            .get(body.identity.as_deref())
+            .map_err(|error| Error::InvalidParameters {
+                error: error.to_string(),
+            })?;
+        // If event request is a creation one and it does not specify a public_key, then a random one must be generated
+        if let bodys::EventRequestBody::Create(creation_req) = &mut body.request {
+            if creation_req.public_key.is_none() {
+                let public_key = self
+                    .node
+                    .add_keys(self.derivator)
+                    .await
+                    .map_err(Error::from_api_error)?;
+                self.key_registry.record(&public_key, self.derivator);
+                creation_req.public_key = Some(public_key.to_str());
+            }
+        }
+        let Ok(request) = body.request.try_into() else {
+            return Err(Error::InvalidParameters {
+                error: "Invalid request".to_owned(),
+            });
+        };
+        let signature = match body.signature {
+            Some(signature) => signature.try_into().map_err(Error::from_api_error)?,
+            None => signer
+                .sign(&request, self.digest_derivator)
+                .await
+                .map_err(|error| Error::InternalServerError {
+                    error: error.to_string(),
+                })?,
+        };
+        self.node
+            .external_request(Signed {
+                content: request,
+                signature,
+            })
+            .await
+            .map_err(Error::from_api_error)
+    }
+
+    /// Submits the Fact request that takes the subject from its current state to the desired one.
+    /// The patch, computed as `taple-patch` does, is sent as the data of the given contract method.
+    /// Returns the id of the request and the patch
+    pub async fn submit_desired_state(
+        &self,
+        subject_id: String,
+        state: Value,
+        method: &str,
+        identity: Option<String>,
+    ) -> Result<(DigestIdentifier, Value), Error> {
+        let Ok(id) = DigestIdentifier::from_str(&subject_id) else {
+            return Err(Error::InvalidParameters {
+                error: "ID specified is not a valid Digest Identifier".to_owned(),
+            });
+        };
+        let subject = self
+            .node
+            .get_subject(id)
+            .await
+            .map_err(Error::from_api_error)?;
+        let patch = json_patch::diff(&subject.properties.0, &state);
+        if patch.0.is_empty() {
+            return Err(Error::InvalidParameters {
+                error: "The desired state is equal to the current one".to_owned(),
+            });
+        }
+        let patch = serde_json::to_value(patch).expect("Serialize JSON patch");
+        let body = PostEventRequestBodyPreSignature {
+            request: bodys::EventRequestBody::Fact(FactRequestBody {
+                subject_id,
+                payload: json!({ method: { "data": patch } }),
+            }),
+            signature: None,
+            identity,
+        };
+        let request_id = self.submit(body).await?;
+        Ok((request_id, patch))
+    }
+}
+
+/// Waits until the request finishes or the timeout, capped to [`MAX_WAIT`], expires.
+/// Returns the last known state of the request.
+pub async fn wait_for_request(
+    node: &Api,
+    notifications: &NotificationHub,
+    id: DigestIdentifier,
+    wait: Duration,
+) -> Result<TapleRequest, ApiError> {
+    let deadline = Instant::now() + wait.min(MAX_WAIT);
+    // Subscribed before the first check so no notification is missed
+    let mut receiver = notifications.subscribe();
+    loop {
+        match node.get_request(id.clone()).await {
+            Ok(request)
+                if !matches!(request.state, RequestState::Processing)
+                    || Instant::now() >= deadline =>
+            {
+                return Ok(request)
+            }
+            Ok(_) => {}
+            // Right after being submitted the request may not be registered yet
+            Err(ApiError::NotFound(_)) if Instant::now() < deadline => {}
+            Err(error) => return Err(error),
+        }
+        let next_check = deadline.min(Instant::now() + WAIT_POLL_INTERVAL);
+        // Any notification may mean the request has progressed, so the state is checked again
+        if let Ok(Err(RecvError::Closed)) = timeout_at(next_check, receiver.recv()).await {
+            tokio::time::sleep_until(next_check).await;
+        }
+    }
+}
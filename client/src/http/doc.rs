@@ -1,6 +1,9 @@
 use super::api::bodys::*;
 use super::api::handlers::*;
+use super::api::pagination::*;
 use super::api::responses::*;
+use super::health::*;
+use crate::keys::KeyAlgorithms;
 
 use std::sync::Arc;
 use utoipa::OpenApi;
@@ -23,16 +26,41 @@ use warp::{
         get_allowed_subjects_handler,
         get_subjects_handler,
         get_approval_handler,
+        get_approval_decision_handler,
         get_approvals_handler,
         get_event_handler,
+        get_governance_members_handler,
+        get_governance_policies_handler,
+        get_governance_roles_handler,
+        get_governance_schemas_handler,
+        get_health_handler,
         get_events_of_subject_handler,
+        get_notifications_sse_handler,
+        get_notifications_ws_handler,
+        get_readiness_handler,
         get_subject_handler,
+        get_subject_state_handler,
+        get_subject_verification_handler,
         get_taple_request_handler,
         get_taple_request_state_handler,
         get_validation_proof_handle,
+        get_webhook_dead_letters_handler,
+        get_webhooks_handler,
+        delete_webhook_handler,
         patch_approval_handler,
+        patch_approvals_handler,
         post_event_request_handler,
+        post_event_request_batch_handler,
+        post_desired_state_request_handler,
+        post_event_request_dry_run_handler,
         post_generate_keys_handler,
+        get_keys_handler,
+        get_key_handler,
+        delete_key_handler,
+        get_identities_handler,
+        post_validation_proof_verification_handler,
+        post_webhook_handler,
+        post_webhook_redelivery_handler,
         put_allowed_subjects_handler,
     ),
     components(
@@ -49,19 +77,61 @@ use warp::{
             EventRequestBody,
             EventContentResponse,
             SubjectDataResponse,
+            SubjectStateResponse,
+            GovernanceMemberResponse,
+            GovernanceRoleResponse,
+            RoleWhoResponse,
+            RoleSchemaResponse,
+            GovernanceSchemaResponse,
+            GovernanceContractResponse,
+            GovernancePolicyResponse,
+            PolicyStageResponse,
+            PolicyQuorumResponse,
+            VerificationReportResponse,
+            BrokenLinkResponse,
             TransferRequestBody,
             EOLRequestBody,
             TapleRequestStateResponse,
             RequestStateResponse,
             ApprovalStateResponse,
             ApprovalEntityResponse,
+            ApprovalDecisionResponse,
+            KeyResponse,
+            KeyDetailsResponse,
+            KeySubjectResponse,
+            KeyAlgorithms,
+            IdentityResponse,
             TapleRequestResponse,
             AuthorizeSubjectBody,
             PreauthorizedSubjectsResponse,
             ValidationProofResponse,
             PatchVoteBody,
+            ApprovalFilterBody,
+            BulkVoteBody,
+            ApprovalVoteResultResponse,
             GetProofResponse,
+            VerifyProofBody,
+            QuorumBody,
+            ProofVerificationResponse,
+            SignatureCheckResponse,
             PostEventRequestBodyPreSignature,
+            EventRequestResultResponse,
+            DesiredStateRequestBody,
+            DesiredStateResponse,
+            DryRunProblem,
+            DryRunResponse,
+            NotificationResponse,
+            SubjectsPage,
+            EventsPage,
+            ApprovalsPage,
+            PreauthorizedSubjectsPage,
+            HealthResponse,
+            ReadinessResponse,
+            CheckResponse,
+            PostWebhookBody,
+            WebhookResponse,
+            WebhookDeliveryResponse,
+            SchemaViolationResponse,
             ErrorResponse
         )
     ),
@@ -70,6 +140,10 @@ use warp::{
         (name = "Approvals"),
         (name = "Requests"),
         (name = "Subjects"),
+        (name = "Governances"),
+        (name = "Notifications"),
+        (name = "Health"),
+        (name = "Webhooks"),
         (name = "Others"),
     )
 )]
@@ -0,0 +1,87 @@
+use warp::{hyper::StatusCode, Filter, Rejection, Reply};
+
+use super::api::responses::{HealthResponse, ReadinessResponse};
+use crate::health::Readiness;
+
+pub fn routes(
+    readiness: Readiness,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let health = warp::path!("health")
+        .and(warp::get())
+        .and_then(get_health_handler);
+    let ready = warp::path!("ready")
+        .and(warp::get())
+        .and(warp::any().map(move || readiness.clone()))
+        .and_then(get_readiness_handler);
+    health.or(ready)
+}
+
+/// Liveness
+/// Succeeds while the process is running
+#[utoipa::path(
+    get,
+    path = "/health",
+    operation_id = "Get Health",
+    tag = "Health",
+    responses(
+        (status = 200, description = "The process is running", body = HealthResponse,
+        example = json!(
+            {
+                "status": "up"
+            }
+        )),
+    )
+)]
+pub async fn get_health_handler() -> Result<Box<dyn warp::Reply>, Rejection> {
+    Ok(Box::new(warp::reply::json(&HealthResponse {
+        status: "up".into(),
+    })))
+}
+
+/// Readiness
+/// Checks that the database is readable and writable, the node is running and the
+/// listen addresses are bound
+#[utoipa::path(
+    get,
+    path = "/ready",
+    operation_id = "Get Readiness",
+    tag = "Health",
+    responses(
+        (status = 200, description = "The node is ready", body = ReadinessResponse,
+        example = json!(
+            {
+                "status": "up",
+                "checks": {
+                    "database": { "status": "up" },
+                    "node": { "status": "up" },
+                    "network": { "status": "up" }
+                }
+            }
+        )),
+        (status = 503, description = "Some dependency of the node is failing", body = ReadinessResponse,
+        example = json!(
+            {
+                "status": "down",
+                "checks": {
+                    "database": { "status": "up" },
+                    "node": { "status": "down", "error": "Node is not running" },
+                    "network": { "status": "up" }
+                }
+            }
+        )),
+    )
+)]
+pub async fn get_readiness_handler(
+    readiness: Readiness,
+) -> Result<Box<dyn warp::Reply>, Rejection> {
+    let checks = readiness.check().await;
+    let status = if checks.iter().all(|check| check.result.is_ok()) {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok(Box::new(warp::reply::with_status(
+        warp::reply::json(&ReadinessResponse::from(checks)),
+        status,
+    )))
+}
@@ -1,71 +1,197 @@
 pub mod api;
 pub mod doc;
+pub mod health;
+pub mod tls;
 
 pub use api::routes;
-use std::{net::SocketAddr, sync::Arc};
+use std::{error::Error, net::SocketAddr, sync::Arc, time::Duration};
 use taple_core::{crypto::KeyPair, Api};
 use tokio_util::sync::CancellationToken;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::Config;
-use warp::Filter;
+use warp::{Filter, Rejection, Reply};
 
 use crate::{
+    approvals::ApprovalPolicy,
+    database::leveldb::LDBCollection,
+    health::Readiness,
     http::{
         self,
+        api::{
+            auth::{with_scope, ApiKeys, Scope},
+            handle_rejection,
+            idempotency::IdempotencyStore,
+            submission::EventRequestSubmitter,
+        },
         doc::{serve_swagger, ApiDoc},
     },
+    keys::KeyRegistry,
+    metrics,
+    notifications::NotificationHub,
     settings::ClientSettings,
+    signer::{Signer, Signers},
+    webhooks::Webhooks,
 };
 
+/// Components of the client used by the HTTP server
+pub struct Services {
+    pub notifications: NotificationHub,
+    pub readiness: Readiness,
+    pub webhooks: Webhooks,
+    pub approvals: ApprovalPolicy,
+    pub keys: KeyRegistry,
+}
+
 pub fn build(
     settings: ClientSettings,
     taple_api: Api,
     keys: KeyPair,
+    services: Services,
+    client_db: Arc<LDBCollection>,
     cancellation_token: CancellationToken,
-) {
-    let http_addr = format!("{}:{}", &settings.http_addr, &settings.http_port)
-        .parse::<SocketAddr>()
-        .unwrap();
-
-    let client_api = http::api::routes(
-        taple_api,
-        keys,
+) -> Result<(), Box<dyn Error>> {
+    let http_addr =
+        format!("{}:{}", &settings.http_addr, &settings.http_port).parse::<SocketAddr>()?;
+
+    let tls_config = match &settings.tls {
+        Some(tls_settings) => Some(tls::server_config(tls_settings)?),
+        None => None,
+    };
+
+    let api_keys = ApiKeys::new(settings.api_key_header.clone(), settings.api_keys.clone())
+        .with_client_identities(settings.tls_client_identities.clone());
+    if !api_keys.is_enabled() {
+        log::warn!("No API key configured. The API REST is not protected");
+    }
+    let doc_api_keys = if settings.doc_public {
+        ApiKeys::disabled()
+    } else {
+        api_keys.clone()
+    };
+    let metrics_api_keys = if settings.metrics_public {
+        ApiKeys::disabled()
+    } else {
+        api_keys.clone()
+    };
+
+    let metrics = warp::path!("metrics")
+        .and(warp::get())
+        .and(enabled(settings.metrics))
+        .and(with_scope(metrics_api_keys, Scope::Read))
+        .map(|| {
+            warp::reply::with_header(
+                metrics::render(),
+                "Content-Type",
+                "text/plain; version=0.0.4",
+            )
+        });
+
+    let health = health::routes(services.readiness.clone());
+
+    let idempotency = IdempotencyStore::new(
+        client_db,
+        Duration::from_secs(settings.idempotency_retention),
+    );
+    idempotency.spawn_purge(cancellation_token.clone());
+
+    let signer = Signer::new(&settings.signer, keys);
+    if matches!(signer, Signer::External(_)) {
+        log::info!("Event requests without signature are signed by an external signer");
+    }
+    let signers = Signers::load(
+        signer,
+        &settings.identities,
+        settings.keystore_passphrase_file.as_deref(),
+    )?;
+    let submitter = EventRequestSubmitter::new(
+        taple_api.clone(),
+        signers,
+        services.keys.clone(),
         settings.subjects_key_derivator,
         settings.taple.node.digest_derivator,
     );
 
+    let client_api = http::api::routes(taple_api, submitter, api_keys, idempotency, services);
+
     if settings.doc {
         let openapi_json = warp::path!("doc" / "json")
             .and(warp::get())
+            .and(with_scope(doc_api_keys.clone(), Scope::Read))
             .map(|| warp::reply::json(&ApiDoc::openapi()));
 
         let swagger_ui = warp::path("doc")
             .and(warp::get())
+            .and(with_scope(doc_api_keys, Scope::Read))
             .and(warp::path::full())
             .and(warp::path::tail())
             .and(warp::any().map(move || Arc::new(Config::from("/doc/json"))))
             .and_then(serve_swagger);
 
-        let routes = openapi_json.or(swagger_ui).or(client_api);
-
-        let (_, server) = warp::serve(routes).bind_with_graceful_shutdown(http_addr, async move {
-            cancellation_token.cancelled().await;
-        });
+        let routes = health
+            .or(metrics)
+            .or(openapi_json)
+            .or(swagger_ui)
+            .or(client_api)
+            .recover(handle_rejection)
+            .with(warp::log::custom(metrics::observe_request));
 
-        tokio::spawn(server);
+        serve(routes, http_addr, tls_config, cancellation_token)?;
     } else {
-        let routes = client_api;
+        let routes = health
+            .or(metrics)
+            .or(client_api)
+            .recover(handle_rejection)
+            .with(warp::log::custom(metrics::observe_request));
 
-        let (_, server) = warp::serve(routes).bind_with_graceful_shutdown(http_addr, async move {
-            cancellation_token.cancelled().await;
-        });
-
-        tokio::spawn(server);
+        serve(routes, http_addr, tls_config, cancellation_token)?;
     }
 
+    let protocol = if settings.tls.is_some() {
+        "HTTPS"
+    } else {
+        "HTTP"
+    };
     log::info!(
-        "HTTP server listen on {}:{}",
+        "{} server listen on {}:{}",
+        protocol,
         settings.http_addr,
         settings.http_port
     );
+    Ok(())
+}
+
+/// Rejects every request when the flag is not set, as if the route did not exist
+fn enabled(flag: bool) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::any()
+        .and_then(move || async move {
+            if flag {
+                Ok(())
+            } else {
+                Err(warp::reject::not_found())
+            }
+        })
+        .untuple_one()
+}
+
+fn serve<F>(
+    routes: F,
+    http_addr: SocketAddr,
+    tls_config: Option<Arc<tokio_rustls::rustls::ServerConfig>>,
+    cancellation_token: CancellationToken,
+) -> Result<(), Box<dyn Error>>
+where
+    F: Filter + Clone + Send + Sync + 'static,
+    F::Extract: Reply,
+{
+    match tls_config {
+        Some(tls_config) => tls::serve(routes, http_addr, tls_config, cancellation_token)?,
+        None => {
+            let (_, server) =
+                warp::serve(routes).try_bind_with_graceful_shutdown(http_addr, async move {
+                    cancellation_token.cancelled().await;
+                })?;
+            tokio::spawn(server);
+        }
+    }
+    Ok(())
 }
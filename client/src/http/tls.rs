@@ -0,0 +1,192 @@
+use std::{fs::File, io::BufReader, net::SocketAddr, sync::Arc, time::Duration};
+
+use thiserror::Error;
+use tokio::net::TcpListener;
+use tokio_rustls::{
+    rustls::{
+        server::{AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient},
+        Certificate, PrivateKey, RootCertStore, ServerConfig,
+    },
+    TlsAcceptor,
+};
+use tokio_util::sync::CancellationToken;
+use warp::{
+    hyper::{server::conn::Http, service::service_fn, service::Service, Body, Request},
+    Filter, Reply,
+};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::settings::TlsSettings;
+
+/// Pause after a failed accept, so errors such as running out of file descriptors do not
+/// make the loop spin
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+#[derive(Error, Debug)]
+pub enum TlsError {
+    #[error("Error reading {0}: {1}")]
+    ReadError(String, std::io::Error),
+    #[error("No certificate found in {0}")]
+    NoCertificates(String),
+    #[error("No private key found in {0}")]
+    NoPrivateKey(String),
+    #[error("Invalid client CA certificate: {0}")]
+    InvalidClientCa(String),
+    #[error("Invalid TLS configuration: {0}")]
+    InvalidConfiguration(String),
+    #[error("Error binding {0}: {1}")]
+    BindError(SocketAddr, std::io::Error),
+}
+
+/// Identity of the caller, taken from the Common Name of the verified client certificate
+#[derive(Clone, Debug)]
+pub struct ClientIdentity(pub String);
+
+pub fn server_config(settings: &TlsSettings) -> Result<Arc<ServerConfig>, TlsError> {
+    let certs = load_certs(&settings.cert)?;
+    let key = load_key(&settings.key)?;
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match &settings.client_ca {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(path)? {
+                roots
+                    .add(&cert)
+                    .map_err(|error| TlsError::InvalidClientCa(error.to_string()))?;
+            }
+            if settings.client_auth_required {
+                builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+            } else {
+                builder.with_client_cert_verifier(
+                    AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed(),
+                )
+            }
+        }
+        None if settings.client_auth_required => {
+            return Err(TlsError::InvalidConfiguration(
+                "client certificates can not be required without a client CA".to_owned(),
+            ))
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut config = builder
+        .with_single_cert(certs, key)
+        .map_err(|error| TlsError::InvalidConfiguration(error.to_string()))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+fn open(path: &str) -> Result<BufReader<File>, TlsError> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|error| TlsError::ReadError(path.to_owned(), error))
+}
+
+fn load_certs(path: &str) -> Result<Vec<Certificate>, TlsError> {
+    let certs = rustls_pemfile::certs(&mut open(path)?)
+        .map_err(|error| TlsError::ReadError(path.to_owned(), error))?;
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificates(path.to_owned()));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn load_key(path: &str) -> Result<PrivateKey, TlsError> {
+    let items = rustls_pemfile::read_all(&mut open(path)?)
+        .map_err(|error| TlsError::ReadError(path.to_owned(), error))?;
+    items
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| TlsError::NoPrivateKey(path.to_owned()))
+}
+
+fn client_identity(cert: &Certificate) -> Option<ClientIdentity> {
+    let (_, cert) = X509Certificate::from_der(&cert.0).ok()?;
+    let common_name = cert.subject().iter_common_name().next()?;
+    common_name
+        .as_str()
+        .ok()
+        .map(|name| ClientIdentity(name.to_owned()))
+}
+
+/// Serves the routes over TLS. The identity of verified client certificates is attached
+/// to each request as a [`ClientIdentity`] extension.
+pub fn serve<F>(
+    routes: F,
+    addr: SocketAddr,
+    config: Arc<ServerConfig>,
+    cancellation_token: CancellationToken,
+) -> Result<(), TlsError>
+where
+    F: Filter + Clone + Send + Sync + 'static,
+    F::Extract: Reply,
+{
+    let listener = std::net::TcpListener::bind(addr)
+        .and_then(|listener| {
+            listener.set_nonblocking(true)?;
+            TcpListener::from_std(listener)
+        })
+        .map_err(|error| TlsError::BindError(addr, error))?;
+    let acceptor = TlsAcceptor::from(config);
+    let service = warp::service(routes);
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, peer) = tokio::select! {
+                _ = cancellation_token.cancelled() => break,
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(error) => {
+                        log::warn!("Error accepting connection: {}", error);
+                        tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                        continue;
+                    }
+                },
+            };
+            let acceptor = acceptor.clone();
+            let service = service.clone();
+            let cancellation_token = cancellation_token.clone();
+            tokio::spawn(async move {
+                let stream = match acceptor.accept(stream).await {
+                    Ok(stream) => stream,
+                    Err(error) => {
+                        log::debug!("TLS handshake with {} failed: {}", peer, error);
+                        return;
+                    }
+                };
+                let identity = stream
+                    .get_ref()
+                    .1
+                    .peer_certificates()
+                    .and_then(|certs| certs.first())
+                    .and_then(client_identity);
+                let service = service_fn(move |mut request: Request<Body>| {
+                    if let Some(identity) = &identity {
+                        request.extensions_mut().insert(identity.clone());
+                    }
+                    service.clone().call(request)
+                });
+                let connection = Http::new()
+                    .serve_connection(stream, service)
+                    .with_upgrades();
+                tokio::pin!(connection);
+                let result = tokio::select! {
+                    result = connection.as_mut() => result,
+                    _ = cancellation_token.cancelled() => {
+                        connection.as_mut().graceful_shutdown();
+                        connection.await
+                    }
+                };
+                if let Err(error) = result {
+                    log::debug!("Connection with {} closed: {}", peer, error);
+                }
+            });
+        }
+    });
+    Ok(())
+}
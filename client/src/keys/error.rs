@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone)]
+pub enum KeyRegistryError {
+    #[error("Key {0} not found")]
+    NotFound(String),
+    #[error("Key {0} is already retired")]
+    AlreadyRetired(String),
+    #[error("Key {0} still controls the active subjects {1}")]
+    InUse(String, String),
+    #[error("{0}")]
+    Node(String),
+    #[error("Database error: {0}")]
+    Database(String),
+}
@@ -0,0 +1,199 @@
+mod error;
+
+use std::sync::Arc;
+
+pub use error::KeyRegistryError;
+use serde::{Deserialize, Serialize};
+use taple_core::{
+    identifier::{Derivable, KeyIdentifier},
+    Api, DatabaseCollection, KeyDerivator, SubjectData,
+};
+use utoipa::ToSchema;
+
+use crate::{
+    database::leveldb::LDBCollection,
+    utils::{unix_time, Batches},
+};
+
+const KEYS_PREFIX: &str = "keys/registry/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub enum KeyAlgorithms {
+    /// Ed25519 algorithm
+    Ed25519,
+    /// Secp256k1 algorithm
+    Secp256k1,
+}
+
+impl From<KeyDerivator> for KeyAlgorithms {
+    fn from(val: KeyDerivator) -> Self {
+        match val {
+            KeyDerivator::Ed25519 => KeyAlgorithms::Ed25519,
+            KeyDerivator::Secp256k1 => KeyAlgorithms::Secp256k1,
+        }
+    }
+}
+
+impl From<KeyAlgorithms> for KeyDerivator {
+    fn from(val: KeyAlgorithms) -> Self {
+        match val {
+            KeyAlgorithms::Ed25519 => KeyDerivator::Ed25519,
+            KeyAlgorithms::Secp256k1 => KeyDerivator::Secp256k1,
+        }
+    }
+}
+
+/// Key pair generated by the node for the subjects
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRecord {
+    pub public_key: String,
+    pub derivator: KeyAlgorithms,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    /// The key was found on a subject of the node instead of being recorded when generated,
+    /// so `created_at` is the time it was found
+    #[serde(default)]
+    pub backfilled: bool,
+    /// Seconds since the Unix epoch at which the key was retired
+    pub retired_at: Option<u64>,
+}
+
+/// Registry of the key pairs generated by the node, stored in the database of the client.
+/// The node keeps the private keys but does not list them, so every key generated through
+/// the client is recorded here. Keys generated before the registry existed are recorded
+/// on startup from the subjects the node owns. Keys never assigned to a subject remain unknown
+#[derive(Clone)]
+pub struct KeyRegistry {
+    node: Api,
+    db: Arc<LDBCollection>,
+}
+
+impl KeyRegistry {
+    pub fn new(node: Api, db: Arc<LDBCollection>) -> Self {
+        Self { node, db }
+    }
+
+    /// Records a key pair just generated by the node
+    pub fn record(&self, public_key: &KeyIdentifier, derivator: KeyDerivator) {
+        let record = KeyRecord {
+            public_key: public_key.to_str(),
+            derivator: derivator.into(),
+            created_at: unix_time().as_secs(),
+            backfilled: false,
+            retired_at: None,
+        };
+        if let Err(error) = self.store(&record) {
+            log::error!("Error recording key {}: {}", record.public_key, error);
+        }
+    }
+
+    /// Records the unknown public keys of the subjects owned by the given controller.
+    /// Returns the number of keys recorded
+    pub async fn backfill(&self, controller_id: &str) -> Result<usize, KeyRegistryError> {
+        let mut recorded = 0;
+        for subject in self.all_subjects().await? {
+            if subject.owner.to_str() != controller_id {
+                continue;
+            }
+            let public_key = subject.public_key.to_str();
+            if matches!(self.get(&public_key), Err(KeyRegistryError::NotFound(_))) {
+                self.store(&KeyRecord {
+                    public_key,
+                    derivator: subject.public_key.derivator.into(),
+                    created_at: unix_time().as_secs(),
+                    backfilled: true,
+                    retired_at: None,
+                })?;
+                recorded += 1;
+            }
+        }
+        Ok(recorded)
+    }
+
+    /// Backfills the registry in the background with the keys of the subjects of the node
+    pub fn spawn_backfill(&self, controller_id: String) {
+        let registry = self.clone();
+        tokio::spawn(async move {
+            match registry.backfill(&controller_id).await {
+                Ok(0) => {}
+                Ok(recorded) => log::info!("{} keys of existing subjects recorded", recorded),
+                Err(error) => {
+                    log::error!("Error recording the keys of existing subjects: {}", error)
+                }
+            }
+        });
+    }
+
+    pub fn list(&self, include_retired: bool) -> Vec<KeyRecord> {
+        self.db
+            .iter(false, KEYS_PREFIX.to_owned())
+            .filter_map(|(_, data)| serde_json::from_slice::<KeyRecord>(&data).ok())
+            .filter(|record| include_retired || record.retired_at.is_none())
+            .collect()
+    }
+
+    pub fn get(&self, public_key: &str) -> Result<KeyRecord, KeyRegistryError> {
+        self.db
+            .get(&format!("{}{}", KEYS_PREFIX, public_key))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .ok_or_else(|| KeyRegistryError::NotFound(public_key.to_owned()))
+    }
+
+    /// Subjects whose public key is the given one, whether active or not.
+    /// Transferred subjects have the key of their new owner
+    pub async fn subjects(&self, public_key: &str) -> Result<Vec<SubjectData>, KeyRegistryError> {
+        let mut subjects = self.all_subjects().await?;
+        subjects.retain(|subject| subject.public_key.to_str() == public_key);
+        Ok(subjects)
+    }
+
+    async fn all_subjects(&self) -> Result<Vec<SubjectData>, KeyRegistryError> {
+        let mut subjects = Vec::new();
+        let mut batches = Batches::new(None, false);
+        while let Some(batch) = batches
+            .next(
+                |from, quantity| self.node.get_subjects("".into(), from, Some(quantity)),
+                |subject: &SubjectData| subject.subject_id.to_str(),
+            )
+            .await
+            .map_err(|error| KeyRegistryError::Node(error.to_string()))?
+        {
+            subjects.extend(batch);
+        }
+        Ok(subjects)
+    }
+
+    /// Retires a key that no longer controls any active subject, because they were
+    /// transferred or reached their end of life. The node keeps the private key, but the
+    /// key is no longer listed
+    pub async fn retire(&self, public_key: &str) -> Result<KeyRecord, KeyRegistryError> {
+        let mut record = self.get(public_key)?;
+        if record.retired_at.is_some() {
+            return Err(KeyRegistryError::AlreadyRetired(public_key.to_owned()));
+        }
+        let active: Vec<String> = self
+            .subjects(public_key)
+            .await?
+            .into_iter()
+            .filter(|subject| subject.active)
+            .map(|subject| subject.subject_id.to_str())
+            .collect();
+        if !active.is_empty() {
+            return Err(KeyRegistryError::InUse(
+                public_key.to_owned(),
+                active.join(", "),
+            ));
+        }
+        record.retired_at = Some(unix_time().as_secs());
+        self.store(&record)?;
+        Ok(record)
+    }
+
+    fn store(&self, record: &KeyRecord) -> Result<(), KeyRegistryError> {
+        let data = serde_json::to_vec(record).expect("Serialize key record");
+        self.db
+            .put(&format!("{}{}", KEYS_PREFIX, record.public_key), data)
+            .map_err(|error| KeyRegistryError::Database(error.to_string()))
+    }
+}
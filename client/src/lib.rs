@@ -1,13 +1,30 @@
+mod approvals;
 mod database;
+mod health;
 mod http;
+mod keys;
+mod metrics;
+mod notifications;
 pub mod settings;
+mod signer;
 mod taple;
+mod utils;
+mod webhooks;
 
 use ::futures::Future;
+use approvals::{ApprovalPolicy, RuleSet};
 use database::leveldb::{LDBCollection, LevelDBManager};
+use health::Readiness;
+use keys::KeyRegistry;
+use notifications::NotificationHub;
 use settings::ClientSettings;
+use webhooks::Webhooks;
 
 use std::error::Error;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
 use taple_core::{Node, Notification};
 use tokio_util::sync::CancellationToken;
@@ -16,6 +33,9 @@ use database::leveldb;
 
 pub struct Client {
     taple_node: Node<LevelDBManager, LDBCollection>,
+    notifications: NotificationHub,
+    webhooks: Webhooks,
+    node_running: Arc<AtomicBool>,
     cancellation_token: CancellationToken,
 }
 
@@ -23,14 +43,64 @@ impl Client {
     pub fn build(settings: ClientSettings) -> Result<Self, Box<dyn Error>> {
         let cancellation_token = CancellationToken::new();
 
-        let (taple_node, taple_api, keys) = taple::build(&settings, cancellation_token.clone())?;
+        metrics::init();
+
+        let notifications = NotificationHub::new();
+
+        let (taple_node, taple_api, keys, client_db) =
+            taple::build(&settings, cancellation_token.clone())?;
+
+        let client_db = Arc::new(client_db);
+        let node_running = Arc::new(AtomicBool::new(false));
+
+        let webhooks = Webhooks::new(client_db.clone(), &settings.webhooks);
+        webhooks.spawn_dispatcher(cancellation_token.clone());
+
+        let rules = match &settings.approval_policy {
+            Some(path) => RuleSet::load(path)?,
+            None => RuleSet::default(),
+        };
+        let approvals = ApprovalPolicy::new(taple_api.clone(), client_db.clone(), rules);
+        approvals.spawn(&notifications, cancellation_token.clone());
 
         if settings.http {
-            http::build(settings, taple_api, keys, cancellation_token.clone());
+            let listen_addrs = settings
+                .taple
+                .network
+                .listen_addr
+                .iter()
+                .filter_map(|addr| addr.to_string().ok())
+                .collect();
+            let readiness = Readiness::new(
+                client_db.clone(),
+                taple_api.clone(),
+                node_running.clone(),
+                listen_addrs,
+            );
+            let key_registry = KeyRegistry::new(taple_api.clone(), client_db.clone());
+            key_registry.spawn_backfill(taple_keystore::controller_id(&keys));
+            let services = http::Services {
+                notifications: notifications.clone(),
+                readiness,
+                webhooks: webhooks.clone(),
+                approvals,
+                keys: key_registry,
+            };
+            http::build(
+                settings,
+                taple_api,
+                keys,
+                services,
+                client_db,
+                cancellation_token.clone(),
+            )?;
         }
 
         Ok(Client {
             taple_node,
+            notifications,
+            webhooks,
+            node_running,
             cancellation_token,
         })
     }
@@ -48,9 +118,18 @@ impl Client {
     where
         H: Fn(Notification),
     {
+        let notifications = self.notifications;
+        let webhooks = self.webhooks;
+        self.node_running.store(true, Ordering::Relaxed);
         self.taple_node
-            .handle_notifications(notifications_handler)
+            .handle_notifications(move |notification| {
+                metrics::observe_notification(&notification);
+                webhooks.enqueue(&notification);
+                notifications.publish(notification.clone());
+                notifications_handler(notification);
+            })
             .await;
+        self.node_running.store(false, Ordering::Relaxed);
         self.cancellation_token.cancel();
         log::info!("Stopped");
     }
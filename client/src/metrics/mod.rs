@@ -0,0 +1,178 @@
+use std::time::Instant;
+
+use lazy_static::lazy_static;
+use prometheus::{
+    register_gauge, register_histogram_vec, register_int_counter_vec, register_int_gauge_vec,
+    Encoder, Gauge, HistogramVec, IntCounterVec, IntGaugeVec, TextEncoder,
+};
+use taple_core::Notification;
+use utoipa::OpenApi;
+
+use crate::{http::doc::ApiDoc, notifications::notification_kind};
+
+/// Label of the requests to paths that are not routes of the client
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+lazy_static! {
+    static ref START_TIME: Instant = Instant::now();
+    /// Routes served, as documented in the OpenAPI specification plus the undocumented ones,
+    /// with their parameters as `{id}`
+    static ref ROUTES: Vec<String> = ApiDoc::openapi()
+        .paths
+        .paths
+        .keys()
+        .map(String::as_str)
+        .chain(["/metrics", "/doc", "/doc/json", "/doc/{file}"])
+        .map(|route| {
+            route
+                .split('/')
+                .map(|segment| if segment.starts_with('{') { "{id}" } else { segment })
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .collect();
+    static ref BUILD_INFO: IntGaugeVec = register_int_gauge_vec!(
+        "taple_build_info",
+        "Version of the TAPLE client",
+        &["version"]
+    )
+    .unwrap();
+    static ref UPTIME: Gauge =
+        register_gauge!("taple_uptime_seconds", "Seconds since the client started").unwrap();
+    static ref HTTP_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "taple_http_requests_total",
+        "HTTP requests served",
+        &["method", "route", "status"]
+    )
+    .unwrap();
+    static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "taple_http_request_duration_seconds",
+        "Time spent serving HTTP requests",
+        &["method", "route"]
+    )
+    .unwrap();
+    static ref DATABASE_OPERATIONS: IntCounterVec = register_int_counter_vec!(
+        "taple_database_operations_total",
+        "Operations performed on the database",
+        &["operation"]
+    )
+    .unwrap();
+    static ref DATABASE_OPERATION_DURATION: HistogramVec = register_histogram_vec!(
+        "taple_database_operation_duration_seconds",
+        "Time spent on database operations",
+        &["operation"],
+        vec![0.00001, 0.00005, 0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0]
+    )
+    .unwrap();
+    static ref NOTIFICATIONS: IntCounterVec = register_int_counter_vec!(
+        "taple_notifications_total",
+        "Notifications emitted by the node",
+        &["kind"]
+    )
+    .unwrap();
+}
+
+/// Registers the process information. It must be called when the client starts
+pub fn init() {
+    lazy_static::initialize(&START_TIME);
+    BUILD_INFO
+        .with_label_values(&[env!("CARGO_PKG_VERSION")])
+        .set(1);
+}
+
+pub fn observe_request(info: warp::log::Info) {
+    let method = info.method().as_str();
+    let route = route_template(info.path());
+    HTTP_REQUESTS
+        .with_label_values(&[method, &route, info.status().as_str()])
+        .inc();
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[method, &route])
+        .observe(info.elapsed().as_secs_f64());
+}
+
+/// Route of a path, with its parameters, such as identifiers or sequence numbers, as `{id}`.
+/// Paths that are not routes are reported as unmatched, so the set of labels is bounded
+fn route_template(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').collect();
+    // Fixed segments take precedence over parameters, as in /event-requests/batch
+    ROUTES
+        .iter()
+        .filter_map(|route| {
+            let route_segments: Vec<&str> = route.split('/').collect();
+            let matches = route_segments.len() == segments.len()
+                && route_segments
+                    .iter()
+                    .zip(&segments)
+                    .all(|(route, segment)| *route == "{id}" || route == segment);
+            let fixed_segments = route_segments.iter().filter(|s| **s != "{id}").count();
+            matches.then_some((fixed_segments, route))
+        })
+        .max_by_key(|(fixed_segments, _)| *fixed_segments)
+        .map_or_else(|| UNMATCHED_ROUTE.to_owned(), |(_, route)| route.clone())
+}
+
+/// Runs a database operation, recording its count and latency
+pub fn observe_database<T>(operation: &str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    record_database_operation(operation, start);
+    result
+}
+
+/// Iterator over the database that records the operation when it is dropped, so the latency
+/// includes the consumption of the iterator and not only its creation
+pub struct ObservedIterator<I> {
+    iter: I,
+    operation: &'static str,
+    start: Instant,
+}
+
+impl<I> ObservedIterator<I> {
+    pub fn new(operation: &'static str, create: impl FnOnce() -> I) -> Self {
+        let start = Instant::now();
+        Self {
+            iter: create(),
+            operation,
+            start,
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for ObservedIterator<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+}
+
+impl<I> Drop for ObservedIterator<I> {
+    fn drop(&mut self) {
+        record_database_operation(self.operation, self.start);
+    }
+}
+
+fn record_database_operation(operation: &str, start: Instant) {
+    DATABASE_OPERATIONS.with_label_values(&[operation]).inc();
+    DATABASE_OPERATION_DURATION
+        .with_label_values(&[operation])
+        .observe(start.elapsed().as_secs_f64());
+}
+
+pub fn observe_notification(notification: &Notification) {
+    NOTIFICATIONS
+        .with_label_values(&[notification_kind(notification)])
+        .inc();
+}
+
+/// Metrics in the Prometheus text format
+pub fn render() -> String {
+    UPTIME.set(START_TIME.elapsed().as_secs_f64());
+    let mut buffer = Vec::new();
+    // Encoding into a vector only fails with invalid metric families, which are never registered
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .unwrap();
+    String::from_utf8(buffer).unwrap()
+}
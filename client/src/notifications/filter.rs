@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use futures::Stream;
+use taple_core::{
+    identifier::{Derivable, DigestIdentifier},
+    Api, ApiError, Notification,
+};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+/// Kinds of notification that can be used for filtering
+pub const NOTIFICATION_KINDS: [&str; 6] = [
+    "new_subject",
+    "new_event",
+    "state_updated",
+    "approval_received",
+    "obsoleted_approval",
+    "unrecoverable_error",
+];
+
+pub fn notification_kind(notification: &Notification) -> &'static str {
+    match notification {
+        Notification::NewSubject { .. } => "new_subject",
+        Notification::NewEvent { .. } => "new_event",
+        Notification::StateUpdated { .. } => "state_updated",
+        Notification::ApprovalReceived { .. } => "approval_received",
+        Notification::ObsoletedApproval { .. } => "obsoleted_approval",
+        Notification::UnrecoverableError { .. } => "unrecoverable_error",
+    }
+}
+
+pub fn notification_subject(notification: &Notification) -> Option<&str> {
+    match notification {
+        Notification::NewSubject { subject_id }
+        | Notification::NewEvent { subject_id, .. }
+        | Notification::StateUpdated { subject_id, .. }
+        | Notification::ApprovalReceived { subject_id, .. }
+        | Notification::ObsoletedApproval { subject_id, .. } => Some(subject_id),
+        Notification::UnrecoverableError { .. } => None,
+    }
+}
+
+/// Selection of the notifications a consumer is interested in.
+/// Every criterion that is set must match.
+#[derive(Debug, Clone, Default)]
+pub struct NotificationFilter {
+    subject_id: Option<String>,
+    governance_id: Option<String>,
+    kinds: Option<HashSet<&'static str>>,
+}
+
+impl NotificationFilter {
+    pub fn new(
+        subject_id: Option<String>,
+        governance_id: Option<String>,
+        kinds: Option<String>,
+    ) -> Result<Self, ApiError> {
+        for id in subject_id.iter().chain(governance_id.iter()) {
+            if DigestIdentifier::from_str(id).is_err() {
+                return Err(ApiError::InvalidParameters(format!(
+                    "Invalid digest identifier {}",
+                    id
+                )));
+            }
+        }
+        let kinds = match kinds {
+            Some(kinds) => {
+                let mut result = HashSet::new();
+                for kind in kinds.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+                    let Some(kind) = NOTIFICATION_KINDS.iter().find(|k| **k == kind) else {
+                        return Err(ApiError::InvalidParameters(format!(
+                            "Unknown notification kind {}",
+                            kind
+                        )));
+                    };
+                    result.insert(*kind);
+                }
+                Some(result)
+            }
+            None => None,
+        };
+        Ok(Self {
+            subject_id,
+            governance_id,
+            kinds,
+        })
+    }
+
+    /// Turns a subscription of the [`super::NotificationHub`] into a stream that only
+    /// yields the notifications accepted by the filter.
+    pub fn stream(
+        self,
+        node: Api,
+        receiver: Receiver<Notification>,
+    ) -> impl Stream<Item = Notification> {
+        // Governance of each subject already seen, so the node is queried only once per subject
+        let governances: HashMap<String, String> = HashMap::new();
+        futures::stream::unfold(
+            (self, node, receiver, governances),
+            |(filter, node, mut receiver, mut governances)| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(notification) => {
+                            if filter.matches(&notification, &node, &mut governances).await {
+                                return Some((notification, (filter, node, receiver, governances)));
+                            }
+                        }
+                        Err(RecvError::Lagged(skipped)) => {
+                            log::warn!("Notification subscriber lagged, {} skipped", skipped);
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            },
+        )
+    }
+
+    async fn matches(
+        &self,
+        notification: &Notification,
+        node: &Api,
+        governances: &mut HashMap<String, String>,
+    ) -> bool {
+        if let Some(kinds) = &self.kinds {
+            if !kinds.contains(notification_kind(notification)) {
+                return false;
+            }
+        }
+        if self.subject_id.is_none() && self.governance_id.is_none() {
+            return true;
+        }
+        let Some(subject_id) = notification_subject(notification) else {
+            return false;
+        };
+        if let Some(expected) = &self.subject_id {
+            if expected != subject_id {
+                return false;
+            }
+        }
+        let Some(expected) = &self.governance_id else {
+            return true;
+        };
+        // Notifications about the governance itself are also delivered
+        if expected == subject_id {
+            return true;
+        }
+        if !governances.contains_key(subject_id) {
+            let Ok(id) = DigestIdentifier::from_str(subject_id) else {
+                return false;
+            };
+            let Ok(subject) = node.get_subject(id).await else {
+                return false;
+            };
+            governances.insert(subject_id.to_owned(), subject.governance_id.to_str());
+        }
+        governances.get(subject_id) == Some(expected)
+    }
+}
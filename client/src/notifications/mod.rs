@@ -0,0 +1,37 @@
+mod filter;
+
+pub use filter::{notification_kind, NotificationFilter};
+use taple_core::Notification;
+use tokio::sync::broadcast;
+
+/// Number of notifications kept for each subscriber. Subscribers that fall further
+/// behind lose the oldest notifications instead of blocking the node.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Fan-out point for the notifications produced by the TAPLE node.
+#[derive(Clone)]
+pub struct NotificationHub {
+    sender: broadcast::Sender<Notification>,
+}
+
+impl NotificationHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn publish(&self, notification: Notification) {
+        // An error only means that nobody is listening right now
+        let _ = self.sender.send(notification);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Notification> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for NotificationHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
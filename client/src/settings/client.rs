@@ -1,12 +1,168 @@
+use std::str::FromStr;
+
 use easy_settings::{ParamType, SettingsMap};
 use easy_settings::{SettingSchemaBuilder, SettingsBuilder};
+use taple_core::crypto::{KeyMaterial, KeyPair};
 use taple_core::{DigestDerivator, KeyDerivator, ListenAddr, Settings};
+use taple_keystore::Keystore;
 
 use crate::settings::create_path;
 use crate::settings::SettingsError;
 
 use super::taple::extract_key_derivator;
-use super::{extract_boolean, extract_from_map, extract_list, SettingsGenerator};
+use super::{extract_boolean, extract_from_map, extract_list, extract_option, SettingsGenerator};
+
+/// Permissions granted to a credential of the API REST
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiRole {
+    /// Only queries
+    ReadOnly,
+    /// Queries and event requests
+    Submitter,
+    /// Queries and votes of approval requests
+    Approver,
+    /// Full access, including keys and preauthorized subjects management
+    Admin,
+}
+
+impl FromStr for ApiRole {
+    type Err = SettingsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "read-only" | "readonly" => Ok(ApiRole::ReadOnly),
+            "submitter" => Ok(ApiRole::Submitter),
+            "approver" => Ok(ApiRole::Approver),
+            "admin" => Ok(ApiRole::Admin),
+            other => Err(SettingsError::InvalidApiRole(other.to_owned())),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ApiCredential {
+    pub key: String,
+    pub role: ApiRole,
+}
+
+impl FromStr for ApiCredential {
+    type Err = SettingsError;
+
+    /// Parses "<key> [role]". Keys without role are granted full access
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let Some(key) = parts.next() else {
+            return Err(SettingsError::InvalidTypeParamer("api-key".into()));
+        };
+        let role = match parts.next() {
+            Some(role) => role.parse()?,
+            None => ApiRole::Admin,
+        };
+        if parts.next().is_some() {
+            return Err(SettingsError::InvalidTypeParamer("api-key".into()));
+        }
+        Ok(Self {
+            key: key.to_owned(),
+            role,
+        })
+    }
+}
+
+impl ApiCredential {
+    /// Parses "<Common Name> [role]" of a client certificate. Common Names can contain spaces,
+    /// so the role is the last word only when it is a valid role. Without role, full access is granted
+    pub fn from_client_identity(s: &str) -> Result<Self, SettingsError> {
+        let s = s.trim();
+        let (name, role) = match s.rsplit_once(char::is_whitespace) {
+            Some((name, role)) => match role.parse() {
+                Ok(role) => (name.trim_end(), role),
+                Err(_) => (s, ApiRole::Admin),
+            },
+            None => (s, ApiRole::Admin),
+        };
+        if name.is_empty() {
+            return Err(SettingsError::InvalidTypeParamer(
+                "tls-client-identity".into(),
+            ));
+        }
+        Ok(Self {
+            key: name.to_owned(),
+            role,
+        })
+    }
+}
+
+/// Certificates used by the HTTP server to serve HTTPS
+#[derive(Clone, Debug)]
+pub struct TlsSettings {
+    pub cert: String,
+    pub key: String,
+    /// CA bundle used to verify client certificates. Without it, client certificates are not requested
+    pub client_ca: Option<String>,
+    pub client_auth_required: bool,
+}
+
+/// Webhooks that receive the notifications of the node
+#[derive(Clone, Debug)]
+pub struct WebhookSettings {
+    pub urls: Vec<String>,
+    /// Secret shared with the webhooks to sign the deliveries
+    pub secret: Option<String>,
+    /// Attempts of a delivery before it is kept as a dead letter
+    pub max_attempts: u32,
+}
+
+/// Identity that callers can select to sign their event requests
+#[derive(Clone, Debug)]
+pub struct IdentitySettings {
+    pub name: String,
+    /// Keystore with the key pair of the identity
+    pub keystore: String,
+}
+
+impl FromStr for IdentitySettings {
+    type Err = SettingsError;
+
+    /// Parses "<name> <keystore>"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let [name, keystore] = parts[..] else {
+            return Err(SettingsError::InvalidTypeParamer("identity".into()));
+        };
+        Ok(Self {
+            name: name.to_owned(),
+            keystore: keystore.to_owned(),
+        })
+    }
+}
+
+/// Signer of the event requests that reach the client without signature
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SignerSettings {
+    /// The key pair of the node
+    #[default]
+    Local,
+    /// External signer listening on a Unix socket
+    Socket(String),
+    /// External signer executed for every signature
+    Command(String),
+}
+
+impl FromStr for SignerSettings {
+    type Err = SettingsError;
+
+    /// Parses "local", "unix:<socket>" or "exec:<command>"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "local" => Ok(SignerSettings::Local),
+            Some(("unix", path)) if !path.is_empty() => Ok(SignerSettings::Socket(path.to_owned())),
+            Some(("exec", command)) if !command.trim().is_empty() => {
+                Ok(SignerSettings::Command(command.to_owned()))
+            }
+            _ => Err(SettingsError::InvalidSigner(s.to_owned())),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct ClientSettings {
@@ -15,6 +171,26 @@ pub struct ClientSettings {
     pub http_addr: String,
     pub http_port: u32,
     pub doc: bool,
+    pub doc_public: bool,
+    pub metrics: bool,
+    pub metrics_public: bool,
+    pub api_keys: Vec<ApiCredential>,
+    pub api_key_header: String,
+    pub tls: Option<TlsSettings>,
+    /// Seconds during which an idempotency key is remembered
+    pub idempotency_retention: u64,
+    /// Common Names of client certificates accepted as credentials
+    pub tls_client_identities: Vec<ApiCredential>,
+    pub webhooks: WebhookSettings,
+    /// TOML file with the rules used to vote the requests for approval automatically
+    pub approval_policy: Option<String>,
+    /// File with the passphrase of the keystores of the node identity
+    pub keystore_passphrase_file: Option<String>,
+    /// Keystore with the identity generated on the first start
+    pub identity_file: String,
+    pub signer: SignerSettings,
+    /// Named identities that callers can select to sign their event requests
+    pub identities: Vec<IdentitySettings>,
     pub db_path: String,
     pub subjects_key_derivator: KeyDerivator
 }
@@ -50,6 +226,10 @@ impl SettingsGenerator for ClientSettings {
         };
         let mut taple_settings = Settings::generate(data)?;
         taple_settings.network.listen_addr = listen_addr;
+        if let Some(keys) = extract_keystore(data)? {
+            taple_settings.node.key_derivator = keys.get_key_derivator();
+            taple_settings.node.secret_key = hex::encode(keys.secret_key_bytes());
+        }
         let database_path = create_database_path(data)?;
         Ok(Self {
             taple: taple_settings,
@@ -57,6 +237,38 @@ impl SettingsGenerator for ClientSettings {
             http_addr: extract_from_map(data, "addr", "0.0.0.0".into())?,
             http_port: extract_from_map(data, "port", 3000u32)? + ports_offset,
             doc: extract_from_map(data, "doc", false)?,
+            doc_public: extract_from_map(data, "doc-public", false)?,
+            metrics: extract_from_map(data, "metrics", false)?,
+            metrics_public: extract_from_map(data, "metrics-public", false)?,
+            api_keys: extract_api_keys(data)?,
+            api_key_header: extract_from_map(data, "api-key-header", "X-API-KEY".into())?,
+            tls: extract_tls(data)?,
+            idempotency_retention: extract_from_map(data, "idempotency-retention", 86400u64)?,
+            tls_client_identities: extract_list(data, "tls-client-identity")
+                .iter()
+                .map(|identity| ApiCredential::from_client_identity(identity))
+                .collect::<Result<_, _>>()?,
+            webhooks: WebhookSettings {
+                urls: extract_list(data, "webhook"),
+                secret: extract_option(data, "webhook-secret")?,
+                max_attempts: extract_from_map(data, "webhook-max-attempts", 10u32)?,
+            },
+            approval_policy: extract_option(data, "approval-policy")?,
+            keystore_passphrase_file: extract_option(data, "id-keystore-passphrase-file")?,
+            // Next to the database, whose directory belongs to LevelDB
+            identity_file: extract_from_map(
+                data,
+                "id-file",
+                format!("{}-identity.keystore", database_path.trim_end_matches('/')),
+            )?,
+            signer: match data.get::<String>("signer") {
+                Some(signer) => signer.parse()?,
+                None => SignerSettings::Local,
+            },
+            identities: extract_list(data, "identity")
+                .iter()
+                .map(|identity| identity.parse())
+                .collect::<Result<_, _>>()?,
             db_path: database_path,
             subjects_key_derivator: extract_key_derivator(
                 data,
@@ -82,6 +294,58 @@ fn create_database_path(data: &SettingsMap) -> Result<String, SettingsError> {
     Ok(path)
 }
 
+fn extract_api_keys(data: &SettingsMap) -> Result<Vec<ApiCredential>, SettingsError> {
+    let mut keys = extract_list(data, "api-key");
+    if let Some(path) = extract_option::<_, String>(data, "api-keys-file")? {
+        let content = std::fs::read_to_string(&path)
+            .map_err(|error| SettingsError::FileReadError(path.clone(), error.to_string()))?;
+        // One "<key> [role]" per line. Blank lines and lines starting with '#' are ignored
+        keys.extend(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(String::from),
+        );
+    }
+    keys.iter().map(|key| key.parse()).collect()
+}
+
+/// Decrypts the identity of the node from its keystore, if any
+fn extract_keystore(data: &SettingsMap) -> Result<Option<KeyPair>, SettingsError> {
+    let Some(path) = extract_option::<_, String>(data, "id-keystore")? else {
+        return Ok(None);
+    };
+    if data.get::<String>("id-private-key").is_some() {
+        return Err(SettingsError::ConflictingParameters(
+            "id-private-key".into(),
+            "id-keystore".into(),
+        ));
+    }
+    let keystore = Keystore::read(&path)?;
+    let passphrase_file = extract_option::<_, String>(data, "id-keystore-passphrase-file")?;
+    let passphrase = taple_keystore::passphrase(passphrase_file.as_deref(), false)?;
+    Ok(Some(keystore.decrypt(&passphrase)?))
+}
+
+fn extract_tls(data: &SettingsMap) -> Result<Option<TlsSettings>, SettingsError> {
+    let cert = extract_option::<_, String>(data, "tls-cert")?;
+    let key = extract_option::<_, String>(data, "tls-key")?;
+    let client_ca = extract_option::<_, String>(data, "tls-client-ca")?;
+    let client_auth_required = extract_boolean(data, "tls-client-auth-required", false)?;
+    match (cert, key) {
+        (Some(cert), Some(key)) => Ok(Some(TlsSettings {
+            cert,
+            key,
+            client_ca,
+            client_auth_required,
+        })),
+        (None, None) if client_ca.is_none() => Ok(None),
+        (None, _) => Err(SettingsError::ParameterNotFound("tls-cert".into())),
+        (_, None) => Err(SettingsError::ParameterNotFound("tls-key".into())),
+    }
+}
+
 pub fn client_settings_builder() -> SettingsBuilder {
     let default_settings = Settings::default();
     fn pass_votation_conversion(pass_votation: u8) -> String {
@@ -171,9 +435,98 @@ pub fn client_settings_builder() -> SettingsBuilder {
                     .help("Flag to activate OpenAPI documentation endpoint ")
                     .param_type(ParamType::Flag)
                     .build(),
+                SettingSchemaBuilder::new("doc-public")
+                    .unwrap()
+                    .with_default(false.to_string())
+                    .help("Flag to serve the OpenAPI documentation without API key")
+                    .param_type(ParamType::Flag)
+                    .build(),
+                SettingSchemaBuilder::new("metrics")
+                    .unwrap()
+                    .with_default(false.to_string())
+                    .help("Flag to activate the Prometheus metrics endpoint")
+                    .param_type(ParamType::Flag)
+                    .build(),
+                SettingSchemaBuilder::new("metrics-public")
+                    .unwrap()
+                    .with_default(false.to_string())
+                    .help("Flag to serve the Prometheus metrics without API key")
+                    .param_type(ParamType::Flag)
+                    .build(),
+                SettingSchemaBuilder::new("api-key")
+                    .unwrap()
+                    .help("API key accepted by the API REST, optionally followed by its role (read-only, submitter, approver, admin). If none is set, the API is not protected")
+                    .param_type(ParamType::Multivalued)
+                    .build(),
+                SettingSchemaBuilder::new("api-keys-file")
+                    .unwrap()
+                    .help("File with the API keys accepted by the API REST, one \"<key> [role]\" per line")
+                    .build(),
+                SettingSchemaBuilder::new("api-key-header")
+                    .unwrap()
+                    .help("Header in which the API key is sent")
+                    .build(),
+                SettingSchemaBuilder::new("idempotency-retention")
+                    .unwrap()
+                    .help("Seconds during which the Idempotency-Key of an event request is remembered")
+                    .build(),
+                SettingSchemaBuilder::new("tls-cert")
+                    .unwrap()
+                    .help("PEM file with the certificate chain of the HTTP server. It enables HTTPS")
+                    .build(),
+                SettingSchemaBuilder::new("tls-key")
+                    .unwrap()
+                    .help("PEM file with the private key of the HTTP server certificate")
+                    .build(),
+                SettingSchemaBuilder::new("tls-client-ca")
+                    .unwrap()
+                    .help("PEM file with the CAs used to verify client certificates")
+                    .build(),
+                SettingSchemaBuilder::new("tls-client-auth-required")
+                    .unwrap()
+                    .with_default(false.to_string())
+                    .help("Flag to reject connections without a valid client certificate. It requires tls-client-ca")
+                    .param_type(ParamType::Flag)
+                    .build(),
+                SettingSchemaBuilder::new("tls-client-identity")
+                    .unwrap()
+                    .help("Common Name of a client certificate accepted as credential, optionally followed by its role (read-only, submitter, approver, admin)")
+                    .param_type(ParamType::Multivalued)
+                    .build(),
             ],
         )
         .unwrap()
+        .group(
+            "webhooks",
+            Some("webhooks"),
+            Some("Webhooks that receive the notifications of the node"),
+            vec![
+                SettingSchemaBuilder::new("webhook")
+                    .unwrap()
+                    .help("URL to which every notification is sent with a JSON POST")
+                    .param_type(ParamType::Multivalued)
+                    .build(),
+                SettingSchemaBuilder::new("webhook-secret")
+                    .unwrap()
+                    .help("Secret shared with the webhooks, used to sign the deliveries with HMAC-SHA256")
+                    .build(),
+                SettingSchemaBuilder::new("webhook-max-attempts")
+                    .unwrap()
+                    .help("Attempts of a delivery before it is kept as a dead letter")
+                    .build(),
+            ],
+        )
+        .unwrap()
+        .group(
+            "approvals",
+            Some("approvals"),
+            Some("Automatic votes of the requests for approval"),
+            vec![SettingSchemaBuilder::new("approval-policy")
+                .unwrap()
+                .help("TOML file with the rules used to vote the requests for approval. Requests that no rule matches are left for a manual vote")
+                .build()],
+        )
+        .unwrap()
         .group(
             "experimental",
             Option::<String>::None,
@@ -229,10 +582,42 @@ pub fn client_settings_builder() -> SettingsBuilder {
         .add_setting(
             SettingSchemaBuilder::new("id-private-key")
                 .unwrap()
-                .help("Private Key in hexadecimal to import into the node")
+                .help("Private Key in hexadecimal to import into the node. Without it or a keystore, an identity is generated on the first start and kept in id-file")
                 .short('k')
                 .build(),
         )
+        .add_setting(
+            SettingSchemaBuilder::new("id-keystore")
+                .unwrap()
+                .help("Keystore file, generated with taple-keygen, with the encrypted private key of the node. Its algorithm overrides id-key-derivator")
+                .build(),
+        )
+        .add_setting(
+            SettingSchemaBuilder::new("id-keystore-passphrase-file")
+                .unwrap()
+                .help(format!("File with the passphrase of the keystore. Without it, the passphrase is read from {} or prompted. It also encrypts the identity generated on the first start", taple_keystore::PASSPHRASE_ENV))
+                .build(),
+        )
+        .add_setting(
+            SettingSchemaBuilder::new("id-file")
+                .unwrap()
+                .help("Keystore with the identity generated on the first start. Defaults to <db-path>-identity.keystore")
+                .build(),
+        )
+        .add_setting(
+            SettingSchemaBuilder::new("signer")
+                .unwrap()
+                .help("Signer of the event requests received without signature: local (the node key), unix:<socket> or exec:<command>. The node still signs its own protocol messages with its key")
+                .with_default("local".to_string())
+                .build(),
+        )
+        .add_setting(
+            SettingSchemaBuilder::new("identity")
+                .unwrap()
+                .help("Named identity that callers can select to sign their event requests, as \"<name> <keystore>\". Any credential allowed to submit requests can select it. Keystores are decrypted with the passphrase of id-keystore-passphrase-file")
+                .param_type(ParamType::Multivalued)
+                .build(),
+        )
         .add_setting(
             SettingSchemaBuilder::new("id-key-derivator")
                 .unwrap()
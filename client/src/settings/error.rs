@@ -1,4 +1,5 @@
 use taple_core::ListenAddrErrors;
+use taple_keystore::KeystoreError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -13,8 +14,18 @@ pub enum SettingsError {
     InvalidDigestDerivator,
     #[error("Invalid PassVotation")]
     InvalidPassVotation,
+    #[error("Invalid API role {0}")]
+    InvalidApiRole(String),
+    #[error("Parameters {0} and {1} can not be used together")]
+    ConflictingParameters(String, String),
+    #[error("Invalid signer {0}. Expected local, unix:<socket> or exec:<command>")]
+    InvalidSigner(String),
+    #[error("Error reading file {0}: {1}")]
+    FileReadError(String, String),
     #[error("Folder creation error {0}")]
     FolderCreationError(#[from] std::io::Error),
     #[error("{0}")]
     ListenAddrError(#[from] ListenAddrErrors),
+    #[error("{0}")]
+    KeystoreError(#[from] KeystoreError),
 }
@@ -2,7 +2,10 @@ mod client;
 mod error;
 mod taple;
 
-pub use self::client::{client_settings_builder, ClientSettings};
+pub use self::client::{
+    client_settings_builder, ApiCredential, ApiRole, ClientSettings, IdentitySettings,
+    SignerSettings, TlsSettings, WebhookSettings,
+};
 use easy_settings::SettingsMap;
 pub use error::SettingsError;
 pub use taple::Settings;
@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone)]
+pub enum SignerError {
+    #[error("The signer is not available: {0}")]
+    Unavailable(String),
+    #[error("The signer refused to sign: {0}")]
+    Refused(String),
+    #[error("Invalid response of the signer: {0}")]
+    InvalidResponse(String),
+    #[error("Error signing: {0}")]
+    Signing(String),
+    #[error("Unknown identity {0}")]
+    UnknownIdentity(String),
+    #[error("Identity {0} is defined more than once")]
+    DuplicatedIdentity(String),
+    #[error("Keystore of identity {0} can not be loaded: {1}")]
+    Keystore(String, String),
+}
@@ -0,0 +1,126 @@
+use std::{process::Stdio, str::FromStr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use taple_core::identifier::{Derivable, DigestIdentifier, KeyIdentifier, SignatureIdentifier};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    process::Command,
+    time::timeout,
+};
+
+use super::SignerError;
+
+/// Longest time the client waits for a signature
+const SIGN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Line sent to the signer
+#[derive(Serialize)]
+struct SignRequest {
+    /// Digest to sign, as a DigestIdentifier
+    digest: String,
+}
+
+/// Line answered by the signer
+#[derive(Deserialize)]
+struct SignResponse {
+    /// KeyIdentifier of the key that signed the digest
+    signer: Option<String>,
+    /// SignatureIdentifier of the digest
+    signature: Option<String>,
+    error: Option<String>,
+}
+
+/// Signer that keeps the private key in a separate process. The client sends a JSON line
+/// with the digest to sign and the signer answers with a JSON line with its public key and
+/// the signature, or with an error
+#[derive(Clone)]
+pub enum ExternalSigner {
+    /// Signer listening on a Unix socket. A connection is opened for every signature
+    Socket(String),
+    /// Command executed for every signature, which reads the request from its standard input
+    /// and writes the response to its standard output
+    Command(String),
+}
+
+impl ExternalSigner {
+    pub async fn sign(
+        &self,
+        digest: &DigestIdentifier,
+    ) -> Result<(KeyIdentifier, SignatureIdentifier), SignerError> {
+        let request = serde_json::to_string(&SignRequest {
+            digest: digest.to_str(),
+        })
+        .expect("Serialize sign request");
+        let response = timeout(SIGN_TIMEOUT, self.exchange(request))
+            .await
+            .map_err(|_| SignerError::Unavailable("Timeout".into()))??;
+        let response: SignResponse = serde_json::from_str(&response)
+            .map_err(|error| SignerError::InvalidResponse(error.to_string()))?;
+        if let Some(error) = response.error {
+            return Err(SignerError::Refused(error));
+        }
+        let (Some(signer), Some(signature)) = (response.signer, response.signature) else {
+            return Err(SignerError::InvalidResponse(
+                "The signer and the signature are required".into(),
+            ));
+        };
+        let signer = KeyIdentifier::from_str(&signer)
+            .map_err(|_| SignerError::InvalidResponse("Invalid KeyIdentifier".into()))?;
+        let signature = SignatureIdentifier::from_str(&signature)
+            .map_err(|_| SignerError::InvalidResponse("Invalid SignatureIdentifier".into()))?;
+        Ok((signer, signature))
+    }
+
+    async fn exchange(&self, request: String) -> Result<String, SignerError> {
+        match self {
+            #[cfg(unix)]
+            ExternalSigner::Socket(path) => {
+                let stream = tokio::net::UnixStream::connect(path)
+                    .await
+                    .map_err(|error| SignerError::Unavailable(format!("{}: {}", path, error)))?;
+                let (reader, writer) = stream.into_split();
+                exchange(reader, writer, request).await
+            }
+            #[cfg(not(unix))]
+            ExternalSigner::Socket(_) => Err(SignerError::Unavailable(
+                "Unix sockets are not supported in this platform".into(),
+            )),
+            ExternalSigner::Command(command) => {
+                let mut args = command.split_whitespace();
+                let Some(program) = args.next() else {
+                    return Err(SignerError::Unavailable("Empty command".into()));
+                };
+                let mut child = Command::new(program)
+                    .args(args)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .map_err(|error| SignerError::Unavailable(format!("{}: {}", program, error)))?;
+                let stdin = child.stdin.take().expect("Piped stdin");
+                let stdout = child.stdout.take().expect("Piped stdout");
+                exchange(stdout, stdin, request).await
+            }
+        }
+    }
+}
+
+/// Writes the request line, closing the writer afterwards, and reads the response line
+async fn exchange<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(
+    reader: R,
+    mut writer: W,
+    request: String,
+) -> Result<String, SignerError> {
+    let unavailable = |error: std::io::Error| SignerError::Unavailable(error.to_string());
+    writer
+        .write_all(format!("{}\n", request).as_bytes())
+        .await
+        .map_err(unavailable)?;
+    writer.shutdown().await.map_err(unavailable)?;
+    let mut response = String::new();
+    BufReader::new(reader)
+        .read_line(&mut response)
+        .await
+        .map_err(unavailable)?;
+    Ok(response)
+}
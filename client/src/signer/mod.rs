@@ -0,0 +1,235 @@
+mod error;
+mod external;
+
+use std::{collections::BTreeMap, sync::Arc};
+
+pub use error::SignerError;
+use taple_core::{
+    crypto::KeyPair,
+    identifier::DigestIdentifier,
+    request::EventRequest,
+    signature::{Signature, Signed},
+    DigestDerivator, TimeStamp,
+};
+
+use taple_keystore::Keystore;
+
+use crate::settings::{IdentitySettings, SignerSettings};
+use external::ExternalSigner;
+
+/// Name under which the signer of the node is listed among the identities
+pub const NODE_IDENTITY: &str = "node";
+
+/// Signs the event requests that reach the client without signature
+#[derive(Clone)]
+pub enum Signer {
+    /// The key pair of the node, kept in memory
+    Local(KeyPair),
+    /// A separate process that keeps the private key
+    External(ExternalSigner),
+}
+
+impl Signer {
+    pub fn new(settings: &SignerSettings, keys: KeyPair) -> Self {
+        match settings {
+            SignerSettings::Local => Signer::Local(keys),
+            SignerSettings::Socket(path) => Signer::External(ExternalSigner::Socket(path.clone())),
+            SignerSettings::Command(command) => {
+                Signer::External(ExternalSigner::Command(command.clone()))
+            }
+        }
+    }
+
+    /// Controller id of the key, unknown for external signers until they sign
+    pub fn controller_id(&self) -> Option<String> {
+        match self {
+            Signer::Local(keys) => Some(taple_keystore::controller_id(keys)),
+            Signer::External(_) => None,
+        }
+    }
+
+    pub async fn sign(
+        &self,
+        request: &EventRequest,
+        derivator: DigestDerivator,
+    ) -> Result<Signature, SignerError> {
+        let signer = match self {
+            Signer::Local(keys) => {
+                return Signature::new(request, keys, derivator)
+                    .map_err(|error| SignerError::Signing(error.to_string()))
+            }
+            Signer::External(signer) => signer,
+        };
+        let timestamp = TimeStamp::now();
+        let (content_hash, digest) = external_digest(request, &timestamp, derivator)?;
+        let (signer, value) = signer.sign(&digest).await?;
+        verified(
+            request,
+            Signature {
+                signer,
+                timestamp,
+                value,
+                content_hash,
+            },
+        )
+    }
+}
+
+/// Hash of the request and digest that the node signs, the hash of the content hash and the
+/// timestamp, like `Signature::new` does
+fn external_digest(
+    request: &EventRequest,
+    timestamp: &TimeStamp,
+    derivator: DigestDerivator,
+) -> Result<(DigestIdentifier, DigestIdentifier), SignerError> {
+    let content_hash = DigestIdentifier::from_serializable_borsh(request, derivator)
+        .map_err(|error| SignerError::Signing(error.to_string()))?;
+    let digest = DigestIdentifier::from_serializable_borsh((&content_hash, timestamp), derivator)
+        .map_err(|error| SignerError::Signing(error.to_string()))?;
+    Ok((content_hash, digest))
+}
+
+/// Checks a signature built from the response of an external signer
+fn verified(request: &EventRequest, signature: Signature) -> Result<Signature, SignerError> {
+    let signed = Signed {
+        content: request.clone(),
+        signature,
+    };
+    signed
+        .verify()
+        .map_err(|error| SignerError::InvalidResponse(error.to_string()))?;
+    Ok(signed.signature)
+}
+
+/// Signer of the node and the named identities that callers can select per request
+#[derive(Clone)]
+pub struct Signers {
+    node: Signer,
+    identities: Arc<BTreeMap<String, Signer>>,
+}
+
+impl Signers {
+    /// Decrypts the keystores of the named identities with the passphrase of the node keystores
+    pub fn load(
+        node: Signer,
+        identities: &[IdentitySettings],
+        passphrase_file: Option<&str>,
+    ) -> Result<Self, SignerError> {
+        let mut named = BTreeMap::new();
+        if !identities.is_empty() {
+            let passphrase =
+                taple_keystore::passphrase(passphrase_file, false).map_err(|error| {
+                    SignerError::Keystore(identities[0].name.clone(), error.to_string())
+                })?;
+            for identity in identities {
+                if identity.name == NODE_IDENTITY || named.contains_key(&identity.name) {
+                    return Err(SignerError::DuplicatedIdentity(identity.name.clone()));
+                }
+                let keys = Keystore::read(&identity.keystore)
+                    .and_then(|keystore| keystore.decrypt(&passphrase))
+                    .map_err(|error| {
+                        SignerError::Keystore(identity.name.clone(), error.to_string())
+                    })?;
+                named.insert(identity.name.clone(), Signer::Local(keys));
+            }
+        }
+        Ok(Self {
+            node,
+            identities: Arc::new(named),
+        })
+    }
+
+    /// Signer of an identity. The one of the node if none is given
+    pub fn get(&self, identity: Option<&str>) -> Result<&Signer, SignerError> {
+        match identity {
+            None | Some(NODE_IDENTITY) => Ok(&self.node),
+            Some(name) => self
+                .identities
+                .get(name)
+                .ok_or_else(|| SignerError::UnknownIdentity(name.to_owned())),
+        }
+    }
+
+    /// Names of the identities, starting with the one of the node, and their controller ids
+    pub fn list(&self) -> Vec<(String, Option<String>)> {
+        std::iter::once((NODE_IDENTITY, &self.node))
+            .chain(
+                self.identities
+                    .iter()
+                    .map(|(name, signer)| (name.as_str(), signer)),
+            )
+            .map(|(name, signer)| (name.to_owned(), signer.controller_id()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use serde_json::json;
+    use taple_core::{
+        crypto::{Ed25519KeyPair, KeyGenerator, KeyMaterial, Payload, DSA},
+        identifier::{Derivable, KeyIdentifier, SignatureIdentifier},
+        request::FactRequest,
+        ValueWrapper,
+    };
+
+    use super::*;
+
+    const DERIVATOR: DigestDerivator = DigestDerivator::Blake3_256;
+
+    fn request() -> EventRequest {
+        EventRequest::Fact(FactRequest {
+            subject_id: DigestIdentifier::from_str("J8618wGO7hH4wRuEeL0Ob5XNI9Q73BlCNlV8cWBORq78")
+                .unwrap(),
+            payload: ValueWrapper(json!({ "temperature": 20 })),
+        })
+    }
+
+    /// Signature of an external signer, which signs the bytes of the digest it receives
+    fn external_signature(
+        keys: &KeyPair,
+        request: &EventRequest,
+        timestamp: TimeStamp,
+    ) -> Result<Signature, SignerError> {
+        let (content_hash, digest) = external_digest(request, &timestamp, DERIVATOR)?;
+        let signer = KeyIdentifier::new(keys.get_key_derivator(), &keys.public_key_bytes());
+        let value = keys.sign(Payload::Buffer(digest.derivative())).unwrap();
+        verified(
+            request,
+            Signature {
+                value: SignatureIdentifier::new(signer.to_signature_derivator(), &value),
+                signer,
+                timestamp,
+                content_hash,
+            },
+        )
+    }
+
+    #[test]
+    fn external_signatures_match_the_ones_of_the_node() {
+        let keys = KeyPair::Ed25519(Ed25519KeyPair::from_seed(&[]));
+        let request = request();
+        let local = Signature::new(&request, &keys, DERIVATOR).unwrap();
+        let local = verified(&request, local).expect("Local signature verifies");
+        // With the timestamp of the local signature the external path signs the same digest
+        let external = external_signature(&keys, &request, local.timestamp.clone())
+            .expect("External signature verifies");
+        assert_eq!(external.content_hash, local.content_hash);
+        // Ed25519 signatures are deterministic
+        assert_eq!(external.value, local.value);
+        assert!(external_signature(&keys, &request, TimeStamp(1700000000)).is_ok());
+    }
+
+    #[test]
+    fn external_signatures_of_other_content_are_rejected() {
+        let keys = KeyPair::Ed25519(Ed25519KeyPair::from_seed(&[]));
+        let mut signature = external_signature(&keys, &request(), TimeStamp(1700000000)).unwrap();
+        signature.timestamp = TimeStamp(1700000001);
+        assert!(matches!(
+            verified(&request(), signature),
+            Err(SignerError::InvalidResponse(_))
+        ));
+    }
+}
@@ -0,0 +1,43 @@
+use std::error::Error;
+
+use taple_core::crypto::{Ed25519KeyPair, KeyGenerator, KeyPair, Secp256k1KeyPair};
+use taple_core::KeyDerivator;
+use taple_keystore::Keystore;
+
+use crate::ClientSettings;
+
+/// Key pair of the node. Without a configured private key, the one generated on the first
+/// start is used. It is encrypted with the configured passphrase, if any
+pub fn load_or_create(settings: &ClientSettings) -> Result<KeyPair, Box<dyn Error>> {
+    let node = &settings.taple.node;
+    if !node.secret_key.is_empty() {
+        return KeyPair::from_hex(&node.key_derivator, &node.secret_key)
+            .map_err(|_| "The private key is not valid for the key derivator of the node".into());
+    }
+    let path = &settings.identity_file;
+    let passphrase =
+        taple_keystore::configured_passphrase(settings.keystore_passphrase_file.as_deref())?;
+    if std::path::Path::new(path).exists() {
+        let keys = Keystore::read(path)?.decrypt(&passphrase.unwrap_or_default())?;
+        log::info!("Node identity loaded from {}", path);
+        return Ok(keys);
+    }
+    let keys = match node.key_derivator {
+        KeyDerivator::Ed25519 => KeyPair::Ed25519(Ed25519KeyPair::from_seed(&[])),
+        KeyDerivator::Secp256k1 => KeyPair::Secp256k1(Secp256k1KeyPair::from_seed(&[])),
+    };
+    if passphrase.is_none() {
+        log::warn!(
+            "No keystore passphrase configured. The node identity is only protected by the permissions of {}",
+            path
+        );
+    }
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    Keystore::encrypt(&keys, &passphrase.unwrap_or_default())?.write(path)?;
+    log::info!("Node identity generated and stored in {}", path);
+    log::info!("Controller ID: {}", taple_keystore::controller_id(&keys));
+    log::info!("Peer ID: {}", taple_keystore::peer_id(&keys));
+    Ok(keys)
+}
@@ -1,6 +1,11 @@
-use std::path::Path;
+mod identity;
 
-use taple_core::{crypto::KeyPair, Api, Node};
+use std::{error::Error, path::Path};
+
+use taple_core::{
+    crypto::{KeyMaterial, KeyPair},
+    Api, Node,
+};
 use tokio_util::sync::CancellationToken;
 
 use crate::{
@@ -8,26 +13,31 @@ use crate::{
     ClientSettings,
 };
 
+pub type TapleNode = Node<LevelDBManager, LDBCollection>;
+
+/// Prefix of the keys stored by the client itself in the database of the node
+const CLIENT_DB_PREFIX: &str = "taple-client/";
+
 pub fn build(
     settings: &ClientSettings,
     cancellation_token: CancellationToken,
-) -> Result<(Node<LevelDBManager, LDBCollection>, Api, KeyPair), taple_core::Error> {
+) -> Result<(TapleNode, Api, KeyPair, LDBCollection), Box<dyn Error>> {
     let db = {
         let db = open_db(Path::new(&settings.db_path));
         LevelDBManager::new(db)
     };
+    let client_db = db.create_prefixed_collection(CLIENT_DB_PREFIX);
 
-    let keys = {
-        let derivator = &settings.taple.node.key_derivator;
-        let secret_key = &settings.taple.node.secret_key;
-        KeyPair::from_hex(derivator, secret_key).expect("Key derivated")
-    };
+    let keys = identity::load_or_create(settings)?;
+    let mut taple_settings = settings.taple.clone();
+    taple_settings.node.key_derivator = keys.get_key_derivator();
+    taple_settings.node.secret_key = hex::encode(keys.secret_key_bytes());
 
-    let (taple_node, taple_api) = Node::build(settings.taple.clone(), db)?;
+    let (taple_node, taple_api) = Node::build(taple_settings, db)?;
 
     taple_node.bind_with_shutdown(async move {
         cancellation_token.cancelled().await;
     });
 
-    Ok((taple_node, taple_api, keys))
+    Ok((taple_node, taple_api, keys, client_db))
 }
@@ -0,0 +1,115 @@
+use std::{
+    future::Future,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Number of entries requested to the node at a time while walking one of its lists
+pub const BATCH_SIZE: i64 = 100;
+
+/// Time elapsed since the Unix epoch, or zero if the clock is set before it
+pub fn unix_time() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// Walks a list of the node in batches, requesting each one from the cursor of the last
+/// entry of the previous batch until a batch comes short
+pub struct Batches<C> {
+    cursor: Option<C>,
+    size: i64,
+    complete: bool,
+}
+
+impl<C: Clone> Batches<C> {
+    /// Batches of [`BATCH_SIZE`] entries from `from`. Backwards walks the list towards its start
+    pub fn new(from: Option<C>, backwards: bool) -> Self {
+        Self {
+            cursor: from,
+            size: if backwards { -BATCH_SIZE } else { BATCH_SIZE },
+            complete: false,
+        }
+    }
+
+    /// Requests the next batch with `fetch`, which receives the cursor and the signed size
+    /// of the batch. `cursor` gives the cursor that follows an entry.
+    /// Returns `None` once the list is exhausted
+    pub async fn next<T, E, F, Fut>(
+        &mut self,
+        fetch: F,
+        cursor: impl Fn(&T) -> C,
+    ) -> Result<Option<Vec<T>>, E>
+    where
+        F: FnOnce(Option<C>, i64) -> Fut,
+        Fut: Future<Output = Result<Vec<T>, E>>,
+    {
+        if self.complete {
+            return Ok(None);
+        }
+        let batch = fetch(self.cursor.clone(), self.size).await?;
+        self.complete = (batch.len() as i64) < self.size.abs();
+        match batch.last() {
+            Some(last) => self.cursor = Some(cursor(last)),
+            None => self.complete = true,
+        }
+        Ok(Some(batch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    /// Entries of a list of `len` numbers after the cursor, like the lists of the node
+    async fn fetch(len: i64, cursor: Option<i64>, size: i64) -> Result<Vec<i64>, ()> {
+        let entries = if size > 0 {
+            let start = cursor.map_or(0, |cursor| cursor + 1);
+            (start..len).take(size as usize).collect()
+        } else {
+            let end = cursor.unwrap_or(len);
+            (0..end).rev().take(size.unsigned_abs() as usize).collect()
+        };
+        Ok(entries)
+    }
+
+    fn walk(len: i64, from: Option<i64>, backwards: bool) -> Vec<Vec<i64>> {
+        let mut batches = Batches::new(from, backwards);
+        let mut walked = Vec::new();
+        while let Some(batch) = block_on(batches.next(
+            |cursor, size| fetch(len, cursor, size),
+            |entry: &i64| *entry,
+        ))
+        .unwrap()
+        {
+            walked.push(batch);
+        }
+        walked
+    }
+
+    #[test]
+    fn batches_walk_the_list_until_a_batch_comes_short() {
+        let walked = walk(250, None, false);
+        assert_eq!(
+            walked.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![100, 100, 50]
+        );
+        assert_eq!(walked.concat(), (0..250).collect::<Vec<_>>());
+        // A full last batch is followed by an empty one
+        assert_eq!(
+            walk(200, None, false)
+                .iter()
+                .map(Vec::len)
+                .collect::<Vec<_>>(),
+            vec![100, 100, 0]
+        );
+    }
+
+    #[test]
+    fn batches_walk_backwards_from_the_cursor() {
+        let walked = walk(250, Some(150), true);
+        assert_eq!(walked.concat(), (0..150).rev().collect::<Vec<_>>());
+        assert!(walk(0, None, true).concat().is_empty());
+    }
+}
@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone)]
+pub enum WebhookError {
+    #[error("Invalid webhook URL {0}. It must be an http or https URL")]
+    InvalidUrl(String),
+    #[error("Webhook {0} is already registered")]
+    AlreadyRegistered(String),
+    #[error("Webhook {0} is defined in the settings and can not be removed")]
+    Configured(String),
+    #[error("{0} not found")]
+    NotFound(String),
+    #[error("Database error: {0}")]
+    Database(String),
+}
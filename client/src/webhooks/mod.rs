@@ -0,0 +1,529 @@
+mod error;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+pub use error::WebhookError;
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use taple_core::{DatabaseCollection, Notification};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    database::leveldb::LDBCollection, http::api::responses::NotificationResponse,
+    settings::WebhookSettings, utils::unix_time,
+};
+
+/// Header with the HMAC-SHA256 of `<timestamp>.<body>`, keyed with the secret of the webhook
+pub const SIGNATURE_HEADER: &str = "X-Taple-Signature";
+/// Header with the seconds since the Unix epoch at which the delivery was sent
+pub const TIMESTAMP_HEADER: &str = "X-Taple-Timestamp";
+/// Header with the id of the delivery, the same in every retry
+pub const DELIVERY_HEADER: &str = "X-Taple-Delivery";
+
+const WEBHOOKS_PREFIX: &str = "webhooks/registered/";
+/// Deliveries are keyed by `<next attempt>/<id>`, so the due ones are a prefix of the queue
+const QUEUE_PREFIX: &str = "webhooks/queue/";
+const DEAD_LETTERS_PREFIX: &str = "webhooks/dead-letters/";
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+/// Longest time the dispatcher waits without checking the queue
+const MAX_IDLE_INTERVAL: Duration = Duration::from_secs(60);
+const MAX_CONCURRENT_DELIVERIES: usize = 16;
+
+/// URL that receives the notifications of the node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// Secret used to sign the deliveries. The shared one is used if absent
+    pub secret: Option<String>,
+    /// Set for the webhooks defined in the settings, which can not be removed through the API
+    #[serde(skip)]
+    pub configured: bool,
+}
+
+impl Webhook {
+    fn new(url: String, secret: Option<String>, configured: bool) -> Self {
+        Self {
+            id: webhook_id(&url),
+            url,
+            secret,
+            configured,
+        }
+    }
+}
+
+/// Notification pending to be delivered to a webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub notification: NotificationResponse,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    pub attempts: u32,
+    /// Milliseconds since the Unix epoch
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+}
+
+/// Body of the requests sent to the webhooks
+#[derive(Serialize)]
+struct Payload<'a> {
+    delivery_id: &'a str,
+    created_at: u64,
+    notification: &'a NotificationResponse,
+}
+
+/// Registry of webhooks and durable queue of their deliveries, stored in the database of the client.
+/// Failed deliveries are retried with exponential backoff until they run out of attempts,
+/// then they are kept as dead letters until redelivered.
+#[derive(Clone)]
+pub struct Webhooks {
+    db: Arc<LDBCollection>,
+    configured: Arc<Vec<Webhook>>,
+    /// Webhooks registered through the API, kept in memory to avoid reading them per notification
+    registered: Arc<RwLock<Vec<Webhook>>>,
+    secret: Option<String>,
+    max_attempts: u32,
+    client: reqwest::Client,
+    /// Wakes the dispatcher when deliveries are queued
+    pending: Arc<Notify>,
+    sequence: Arc<AtomicU64>,
+}
+
+impl Webhooks {
+    pub fn new(db: Arc<LDBCollection>, settings: &WebhookSettings) -> Self {
+        let configured = settings
+            .urls
+            .iter()
+            .map(|url| Webhook::new(url.clone(), None, true))
+            .collect();
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .expect("Build webhooks HTTP client");
+        let registered = db
+            .iter(false, WEBHOOKS_PREFIX.to_owned())
+            .filter_map(|(_, data)| serde_json::from_slice::<Webhook>(&data).ok())
+            .collect();
+        Self {
+            db,
+            configured: Arc::new(configured),
+            registered: Arc::new(RwLock::new(registered)),
+            secret: settings.secret.clone(),
+            max_attempts: settings.max_attempts.max(1),
+            client,
+            pending: Arc::new(Notify::new()),
+            sequence: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Webhooks defined in the settings followed by the ones registered through the API
+    pub fn list(&self) -> Vec<Webhook> {
+        let registered = self.registered.read().expect("Webhooks lock poisoned");
+        self.configured
+            .iter()
+            .chain(registered.iter())
+            .cloned()
+            .collect()
+    }
+
+    pub fn register(&self, url: String, secret: Option<String>) -> Result<Webhook, WebhookError> {
+        match reqwest::Url::parse(&url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+            _ => return Err(WebhookError::InvalidUrl(url)),
+        }
+        let webhook = Webhook::new(url, secret, false);
+        let mut registered = self.registered.write().expect("Webhooks lock poisoned");
+        if self
+            .configured
+            .iter()
+            .chain(registered.iter())
+            .any(|other| other.id == webhook.id)
+        {
+            return Err(WebhookError::AlreadyRegistered(webhook.url));
+        }
+        self.store(WEBHOOKS_PREFIX, &webhook.id, &webhook)?;
+        registered.push(webhook.clone());
+        Ok(webhook)
+    }
+
+    /// Removes a webhook. Its pending deliveries are discarded
+    pub fn unregister(&self, id: &str) -> Result<(), WebhookError> {
+        if self.configured.iter().any(|webhook| webhook.id == id) {
+            return Err(WebhookError::Configured(id.to_owned()));
+        }
+        let mut registered = self.registered.write().expect("Webhooks lock poisoned");
+        let Some(position) = registered.iter().position(|webhook| webhook.id == id) else {
+            return Err(WebhookError::NotFound(id.to_owned()));
+        };
+        self.db
+            .del(&format!("{}{}", WEBHOOKS_PREFIX, id))
+            .map_err(|error| WebhookError::Database(error.to_string()))?;
+        registered.remove(position);
+        Ok(())
+    }
+
+    /// Queues the notification for every webhook and wakes the dispatcher to deliver it
+    pub fn enqueue(&self, notification: &Notification) {
+        self.queue(NotificationResponse::from(notification.clone()));
+        self.pending.notify_one();
+    }
+
+    fn queue(&self, notification: NotificationResponse) {
+        let now = unix_time().as_millis() as u64;
+        for webhook in self.list() {
+            let delivery = Delivery {
+                id: self.next_delivery_id(now),
+                webhook_id: webhook.id,
+                notification: notification.clone(),
+                created_at: now / 1000,
+                attempts: 0,
+                next_attempt_at: now,
+                last_error: None,
+            };
+            if let Err(error) = self.store(QUEUE_PREFIX, &queue_key(&delivery), &delivery) {
+                log::error!("Error queuing webhook delivery {}: {}", delivery.id, error);
+            }
+        }
+    }
+
+    pub fn dead_letters(&self) -> Vec<Delivery> {
+        self.db
+            .iter(false, DEAD_LETTERS_PREFIX.to_owned())
+            .filter_map(|(_, data)| serde_json::from_slice::<Delivery>(&data).ok())
+            .collect()
+    }
+
+    /// Moves a dead letter back to the queue, with all its attempts available again
+    pub fn redeliver(&self, id: &str) -> Result<Delivery, WebhookError> {
+        let key = format!("{}{}", DEAD_LETTERS_PREFIX, id);
+        let Some(mut delivery) = self
+            .db
+            .get(&key)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<Delivery>(&data).ok())
+        else {
+            return Err(WebhookError::NotFound(id.to_owned()));
+        };
+        if !self
+            .list()
+            .iter()
+            .any(|webhook| webhook.id == delivery.webhook_id)
+        {
+            return Err(WebhookError::NotFound(delivery.webhook_id));
+        }
+        delivery.attempts = 0;
+        delivery.next_attempt_at = unix_time().as_millis() as u64;
+        self.store(QUEUE_PREFIX, &queue_key(&delivery), &delivery)?;
+        let _ = self.db.del(&key);
+        self.pending.notify_one();
+        Ok(delivery)
+    }
+
+    /// Delivers the queued notifications until the token is cancelled
+    pub fn spawn_dispatcher(&self, cancellation_token: CancellationToken) {
+        let webhooks = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let next_due = webhooks.dispatch_due().await;
+                let idle = next_due
+                    .map(|due| {
+                        Duration::from_millis(due.saturating_sub(unix_time().as_millis() as u64))
+                    })
+                    .unwrap_or(MAX_IDLE_INTERVAL)
+                    .min(MAX_IDLE_INTERVAL);
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => break,
+                    _ = webhooks.pending.notified() => {}
+                    _ = tokio::time::sleep(idle) => {}
+                }
+            }
+        });
+    }
+
+    /// Delivers the due deliveries. Returns when the next one is due, if any
+    async fn dispatch_due(&self) -> Option<u64> {
+        let now = unix_time().as_millis() as u64;
+        let mut next_due = None;
+        let mut due: Vec<(String, Delivery)> = Vec::new();
+        for (key, data) in self.db.iter(false, QUEUE_PREFIX.to_owned()) {
+            let due_at = key
+                .get(..16)
+                .and_then(|due_at| u64::from_str_radix(due_at, 16).ok())
+                .unwrap_or_default();
+            if due_at > now {
+                next_due = Some(due_at);
+                break;
+            }
+            match serde_json::from_slice::<Delivery>(&data) {
+                Ok(delivery) => due.push((key, delivery)),
+                Err(_) => {
+                    let _ = self.db.del(&format!("{}{}", QUEUE_PREFIX, key));
+                }
+            }
+        }
+        if due.is_empty() {
+            return next_due;
+        }
+        let webhooks: HashMap<String, Webhook> = self
+            .list()
+            .into_iter()
+            .map(|webhook| (webhook.id.clone(), webhook))
+            .collect();
+        futures::stream::iter(due)
+            .for_each_concurrent(MAX_CONCURRENT_DELIVERIES, |(key, delivery)| {
+                self.attempt(&webhooks, key, delivery)
+            })
+            .await;
+        // The queue has changed, so it is checked again right away
+        Some(now)
+    }
+
+    async fn attempt(
+        &self,
+        webhooks: &HashMap<String, Webhook>,
+        key: String,
+        mut delivery: Delivery,
+    ) {
+        let key = format!("{}{}", QUEUE_PREFIX, key);
+        let Some(webhook) = webhooks.get(&delivery.webhook_id) else {
+            // The webhook has been removed
+            let _ = self.db.del(&key);
+            return;
+        };
+        delivery.attempts += 1;
+        let result = match self.send(webhook, &delivery).await {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                log::warn!(
+                    "Delivery {} to {} failed: {}",
+                    delivery.id,
+                    webhook.url,
+                    error
+                );
+                delivery.last_error = Some(error);
+                if delivery.attempts >= self.max_attempts {
+                    self.store(DEAD_LETTERS_PREFIX, &delivery.id, &delivery)
+                } else {
+                    delivery.next_attempt_at = unix_time().as_millis() as u64
+                        + retry_delay(delivery.attempts).as_millis() as u64;
+                    self.store(QUEUE_PREFIX, &queue_key(&delivery), &delivery)
+                }
+            }
+        }
+        .and_then(|_| {
+            self.db
+                .del(&key)
+                .map_err(|error| WebhookError::Database(error.to_string()))
+        });
+        if let Err(error) = result {
+            log::error!("Error updating webhook delivery {}: {}", delivery.id, error);
+        }
+    }
+
+    async fn send(&self, webhook: &Webhook, delivery: &Delivery) -> Result<(), String> {
+        let body = serde_json::to_vec(&Payload {
+            delivery_id: &delivery.id,
+            created_at: delivery.created_at,
+            notification: &delivery.notification,
+        })
+        .map_err(|error| error.to_string())?;
+        let timestamp = unix_time().as_millis() as u64 / 1000;
+        let mut request = self
+            .client
+            .post(&webhook.url)
+            .header("Content-Type", "application/json")
+            .header(DELIVERY_HEADER, &delivery.id)
+            .header(TIMESTAMP_HEADER, timestamp.to_string());
+        if let Some(secret) = webhook.secret.as_ref().or(self.secret.as_ref()) {
+            request = request.header(SIGNATURE_HEADER, sign(secret, timestamp, &body));
+        }
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .map_err(|error| error.to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Webhook responded with status {}",
+                response.status()
+            ))
+        }
+    }
+
+    fn store<T: Serialize>(&self, prefix: &str, id: &str, value: &T) -> Result<(), WebhookError> {
+        let data = serde_json::to_vec(value).expect("Serialize webhook data");
+        self.db
+            .put(&format!("{}{}", prefix, id), data)
+            .map_err(|error| WebhookError::Database(error.to_string()))
+    }
+
+    /// Ids sort in the order the deliveries were queued
+    fn next_delivery_id(&self, now: u64) -> String {
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+        format!("{:016x}{:08x}", now, sequence as u32)
+    }
+}
+
+/// Value of the signature header for a delivery
+pub fn sign(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Key of a delivery in the queue, after its prefix
+fn queue_key(delivery: &Delivery) -> String {
+    format!("{:016x}/{}", delivery.next_attempt_at, delivery.id)
+}
+
+fn webhook_id(url: &str) -> String {
+    hex::encode(&Sha256::digest(url.as_bytes())[..8])
+}
+
+fn retry_delay(attempts: u32) -> Duration {
+    FIRST_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::database::leveldb::{open_db, LevelDBManager};
+
+    /// Nothing listens on this port, so the deliveries fail right away
+    const UNREACHABLE_URL: &str = "http://127.0.0.1:1/webhook";
+
+    fn webhooks(path: &Path) -> Webhooks {
+        let db = LevelDBManager::new(open_db(path)).create_prefixed_collection("client");
+        let settings = WebhookSettings {
+            urls: vec![UNREACHABLE_URL.to_owned()],
+            secret: Some("secret".to_owned()),
+            max_attempts: 2,
+        };
+        Webhooks::new(Arc::new(db), &settings)
+    }
+
+    fn queued(webhooks: &Webhooks) -> Vec<Delivery> {
+        webhooks
+            .db
+            .iter(false, QUEUE_PREFIX.to_owned())
+            .filter_map(|(_, data)| serde_json::from_slice::<Delivery>(&data).ok())
+            .collect()
+    }
+
+    #[test]
+    fn sign_is_the_hmac_of_the_timestamp_and_the_body() {
+        assert_eq!(
+            sign("secret", 1700000000, br#"{"kind":"new_subject"}"#),
+            "sha256=ec9debd39e8ade25ceb0445249ca73410fde0a8f07e62d464fc4d53a59de6e6c"
+        );
+        assert_ne!(
+            sign("secret", 1700000001, br#"{"kind":"new_subject"}"#),
+            sign("secret", 1700000000, br#"{"kind":"new_subject"}"#)
+        );
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_maximum() {
+        assert_eq!(retry_delay(0), FIRST_RETRY_DELAY);
+        assert_eq!(retry_delay(1), FIRST_RETRY_DELAY);
+        assert_eq!(retry_delay(2), FIRST_RETRY_DELAY * 2);
+        assert_eq!(retry_delay(5), FIRST_RETRY_DELAY * 16);
+        assert_eq!(retry_delay(20), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(u32::MAX), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn failed_deliveries_are_retried_then_kept_as_dead_letters() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let dir = tempdir().unwrap();
+            let webhooks = webhooks(dir.path());
+            webhooks.queue(NotificationResponse::NewSubject {
+                subject_id: "subject".to_owned(),
+            });
+            let delivery = queued(&webhooks).pop().expect("Delivery queued");
+            assert_eq!(delivery.attempts, 0);
+
+            // The first attempt fails and is scheduled again
+            webhooks.dispatch_due().await;
+            let retried = queued(&webhooks);
+            assert_eq!(retried.len(), 1);
+            assert_eq!(retried[0].id, delivery.id);
+            assert_eq!(retried[0].attempts, 1);
+            assert!(retried[0].last_error.is_some());
+            assert!(retried[0].next_attempt_at > delivery.next_attempt_at);
+
+            // Nothing is due until the retry delay elapses
+            assert_eq!(
+                webhooks.dispatch_due().await,
+                Some(retried[0].next_attempt_at)
+            );
+            assert_eq!(queued(&webhooks)[0].attempts, 1);
+
+            // The last attempt moves it to the dead letters
+            tokio::time::sleep(retry_delay(1)).await;
+            webhooks.dispatch_due().await;
+            assert!(queued(&webhooks).is_empty());
+            let dead_letters = webhooks.dead_letters();
+            assert_eq!(dead_letters.len(), 1);
+            assert_eq!(dead_letters[0].attempts, 2);
+
+            // Redelivery queues it again with every attempt available
+            let redelivered = webhooks.redeliver(&delivery.id).unwrap();
+            assert_eq!(redelivered.attempts, 0);
+            assert!(webhooks.dead_letters().is_empty());
+            assert_eq!(queued(&webhooks).len(), 1);
+            assert!(matches!(
+                webhooks.redeliver(&delivery.id),
+                Err(WebhookError::NotFound(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn deliveries_of_removed_webhooks_are_discarded() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let dir = tempdir().unwrap();
+            let webhooks = webhooks(dir.path());
+            let webhook = webhooks
+                .register("http://127.0.0.1:1/other".to_owned(), None)
+                .unwrap();
+            webhooks.queue(NotificationResponse::NewSubject {
+                subject_id: "subject".to_owned(),
+            });
+            assert_eq!(queued(&webhooks).len(), 2);
+            webhooks.unregister(&webhook.id).unwrap();
+            webhooks.dispatch_due().await;
+            let queued = queued(&webhooks);
+            assert_eq!(queued.len(), 1);
+            assert_ne!(queued[0].webhook_id, webhook.id);
+        });
+    }
+}
@@ -1,34 +1,50 @@
+use std::str::FromStr;
+
 use env_logger::Env;
 
 use easy_settings::SettingsMap;
 
 use taple_client::{
-    settings::{ClientSettings, SettingsGenerator},
+    settings::{
+        ApiCredential, ApiRole, ClientSettings, IdentitySettings, SettingsGenerator, SignerSettings,
+    },
     Client,
 };
 
-use taple_core::crypto::{Ed25519KeyPair, KeyGenerator, KeyMaterial};
+use serde_json::{json, Value};
+use serial_test::serial;
+use taple_core::crypto::{Ed25519KeyPair, KeyGenerator, KeyMaterial, KeyPair, Payload, DSA};
+use taple_core::identifier::{Derivable, DigestIdentifier, KeyIdentifier, SignatureIdentifier};
+use taple_keystore::Keystore;
 use tempfile::tempdir;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
 use tokio::sync::oneshot;
 
+fn test_settings() -> ClientSettings {
+    let _ = env_logger::Builder::from_env(Env::default().default_filter_or("info")).try_init();
+    let mut settings =
+        ClientSettings::generate(&SettingsMap::new()).expect("Create ClientSettings");
+
+    settings.http = true;
+    settings.taple.node.secret_key = {
+        let keypair = Ed25519KeyPair::from_seed(&[]);
+        hex::encode(keypair.secret_key_bytes())
+    };
+
+    settings.db_path = {
+        let db_tempdir = tempdir().unwrap();
+        db_tempdir.path().to_str().unwrap().to_owned()
+    };
+    settings
+}
+
 #[test]
+#[serial]
 fn http_server_working() {
-    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
     let rt = tokio::runtime::Runtime::new().unwrap();
     rt.block_on(async {
-        let mut settings =
-            ClientSettings::generate(&SettingsMap::new()).expect("Create ClientSettings");
-
-        settings.http = true;
-        settings.taple.node.secret_key = {
-            let keypair = Ed25519KeyPair::from_seed(&[]);
-            hex::encode(keypair.secret_key_bytes())
-        };
-
-        settings.db_path = {
-            let db_tempdir = tempdir().unwrap();
-            db_tempdir.path().to_str().unwrap().to_owned()
-        };
+        let settings = test_settings();
 
         let client = Client::build(settings).expect("Client built");
 
@@ -44,3 +60,278 @@ fn http_server_working() {
         client.run(|_| {}).await;
     });
 }
+
+#[test]
+#[serial]
+fn http_server_enforces_api_key_roles() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(async {
+        let mut settings = test_settings();
+        settings.api_keys = vec![ApiCredential {
+            key: "secret".to_owned(),
+            role: ApiRole::ReadOnly,
+        }];
+
+        let client = Client::build(settings).expect("Client built");
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        client.bind_with_shutdown(shutdown_rx);
+
+        let requests = tokio::spawn(async move {
+            let http = reqwest::Client::new();
+            let without_key = http.get("http://127.0.0.1:3000/api/subjects").send().await;
+            let with_key = http
+                .get("http://127.0.0.1:3000/api/subjects")
+                .header("X-API-KEY", "secret")
+                .send()
+                .await;
+            let forbidden = http
+                .post("http://127.0.0.1:3000/api/keys")
+                .header("X-API-KEY", "secret")
+                .send()
+                .await;
+            shutdown_tx.send(()).unwrap();
+            (without_key.unwrap(), with_key.unwrap(), forbidden.unwrap())
+        });
+
+        client.run(|_| {}).await;
+
+        let (without_key, with_key, forbidden) = requests.await.unwrap();
+        assert_eq!(without_key.status(), 401);
+        assert_eq!(with_key.status(), 200);
+        assert_eq!(forbidden.status(), 403);
+    });
+}
+
+#[test]
+#[serial]
+fn http_server_reports_health() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(async {
+        let settings = test_settings();
+
+        let client = Client::build(settings).expect("Client built");
+
+        // The HTTP server is up, but the node is not running yet
+        let not_ready = reqwest::get("http://127.0.0.1:3000/ready").await.unwrap();
+        assert_eq!(not_ready.status(), 503);
+        let not_ready: Value = not_ready.json().await.unwrap();
+        assert_eq!(not_ready["status"], "down");
+        assert_eq!(not_ready["checks"]["node"]["status"], "down");
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        client.bind_with_shutdown(shutdown_rx);
+
+        let requests = tokio::spawn(async move {
+            let health = reqwest::get("http://127.0.0.1:3000/health")
+                .await
+                .unwrap()
+                .status();
+            // The node is marked as running once the client starts
+            let mut ready = None;
+            for _ in 0..20 {
+                let response = reqwest::get("http://127.0.0.1:3000/ready").await.unwrap();
+                let status = response.status();
+                let body: Value = response.json().await.unwrap();
+                ready = Some((status, body));
+                if status == 200 {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+            }
+            shutdown_tx.send(()).unwrap();
+            (health, ready.unwrap())
+        });
+
+        client.run(|_| {}).await;
+
+        let (health, (ready_status, ready)) = requests.await.unwrap();
+        assert_eq!(health, 200);
+        assert_eq!(ready_status, 200, "{}", ready);
+        assert_eq!(ready["status"], "up");
+        assert_eq!(ready["checks"]["database"]["status"], "up");
+        assert_eq!(ready["checks"]["node"]["status"], "up");
+        assert_eq!(ready["checks"]["network"]["status"], "up");
+    });
+}
+
+#[test]
+#[serial]
+fn http_server_signs_with_external_signer() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(async {
+        let mut settings = test_settings();
+        let socket_dir = tempdir().unwrap();
+        let socket = socket_dir.path().join("signer.sock");
+        settings.signer = SignerSettings::Socket(socket.to_str().unwrap().to_owned());
+        let keys = KeyPair::from_hex(
+            &settings.taple.node.key_derivator,
+            &settings.taple.node.secret_key,
+        )
+        .unwrap();
+
+        // Stub signer that signs the first digest it receives with the key of the node
+        let listener = UnixListener::bind(&socket).unwrap();
+        let signer = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut request = String::new();
+            BufReader::new(reader).read_line(&mut request).await.unwrap();
+            let request: Value = serde_json::from_str(&request).unwrap();
+            let digest = DigestIdentifier::from_str(request["digest"].as_str().unwrap()).unwrap();
+            let signer = KeyIdentifier::new(keys.get_key_derivator(), &keys.public_key_bytes());
+            let signature = keys.sign(Payload::Buffer(digest.derivative())).unwrap();
+            let response = json!({
+                "signer": signer.to_str(),
+                "signature": SignatureIdentifier::new(signer.to_signature_derivator(), &signature).to_str(),
+            });
+            writer
+                .write_all(format!("{}\n", response).as_bytes())
+                .await
+                .unwrap();
+        });
+
+        let client = Client::build(settings).expect("Client built");
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        client.bind_with_shutdown(shutdown_rx);
+
+        let request = tokio::spawn(async move {
+            let response = reqwest::Client::new()
+                .post("http://127.0.0.1:3000/api/event-requests")
+                .json(&json!({
+                    "request": {
+                        "Create": {
+                            "governance_id": "",
+                            "schema_id": "governance",
+                            "namespace": "",
+                            "name": "External signer"
+                        }
+                    }
+                }))
+                .send()
+                .await;
+            shutdown_tx.send(()).unwrap();
+            response.unwrap()
+        });
+
+        client.run(|_| {}).await;
+
+        assert_eq!(request.await.unwrap().status(), 200);
+        signer.await.unwrap();
+    });
+}
+
+#[test]
+#[serial]
+fn http_server_selects_signing_identity() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(async {
+        let mut settings = test_settings();
+        let keystore_dir = tempdir().unwrap();
+        let passphrase_file = keystore_dir.path().join("passphrase");
+        std::fs::write(&passphrase_file, "passphrase").unwrap();
+        let keystore = keystore_dir.path().join("logistics.keystore");
+        let logistics = KeyPair::Ed25519(Ed25519KeyPair::from_seed(&[]));
+        Keystore::encrypt(&logistics, "passphrase")
+            .unwrap()
+            .write(keystore.to_str().unwrap())
+            .unwrap();
+        let logistics_id = taple_keystore::controller_id(&logistics);
+        let node_id = taple_keystore::controller_id(
+            &KeyPair::from_hex(
+                &settings.taple.node.key_derivator,
+                &settings.taple.node.secret_key,
+            )
+            .unwrap(),
+        );
+        settings.keystore_passphrase_file = Some(passphrase_file.to_str().unwrap().to_owned());
+        settings.identities = vec![IdentitySettings {
+            name: "logistics".to_owned(),
+            keystore: keystore.to_str().unwrap().to_owned(),
+        }];
+
+        let client = Client::build(settings).expect("Client built");
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        client.bind_with_shutdown(shutdown_rx);
+
+        let requests = tokio::spawn(async move {
+            let http = reqwest::Client::new();
+            let create = |identity: Option<&str>| {
+                json!({
+                    "request": {
+                        "Create": {
+                            "governance_id": "",
+                            "schema_id": "governance",
+                            "namespace": "",
+                            "name": "Signing identity"
+                        }
+                    },
+                    "identity": identity
+                })
+            };
+            let send = |identity: &str, body: Value| {
+                http.post("http://127.0.0.1:3000/api/event-requests")
+                    .header("Signing-Identity", identity)
+                    .json(&body)
+                    .send()
+            };
+            let selected = send("logistics", create(None)).await.unwrap();
+            let selected_status = selected.status();
+            let selected: Value = selected.json().await.unwrap();
+            let request = http
+                .get(format!(
+                    "http://127.0.0.1:3000/api/event-requests/{}",
+                    selected["request_id"].as_str().unwrap_or_default()
+                ))
+                .send()
+                .await
+                .unwrap()
+                .json::<Value>()
+                .await
+                .unwrap();
+            let unknown = send("unknown", create(None)).await.unwrap();
+            let mismatch = send("logistics", create(Some("node"))).await.unwrap();
+            let batch = http
+                .post("http://127.0.0.1:3000/api/event-requests/batch")
+                .header("Signing-Identity", "unknown")
+                .json(&json!([create(None)]))
+                .send()
+                .await
+                .unwrap();
+            let desired_state = http
+                .post("http://127.0.0.1:3000/api/event-requests/desired-state")
+                .header("Signing-Identity", "logistics")
+                .json(&json!({
+                    "subject_id": "J8618wGO7hH4wRuEeL0Ob5XNI9Q73BlCNlV8cWBORq78",
+                    "state": {},
+                    "identity": "node"
+                }))
+                .send()
+                .await
+                .unwrap();
+            shutdown_tx.send(()).unwrap();
+            (
+                selected_status,
+                request,
+                unknown.status(),
+                mismatch.status(),
+                batch.json::<Value>().await.unwrap(),
+                desired_state.status(),
+            )
+        });
+
+        client.run(|_| {}).await;
+
+        let (selected, request, unknown, mismatch, batch, desired_state) = requests.await.unwrap();
+        assert_eq!(selected, 200);
+        // The request is signed by the selected identity instead of the node
+        assert_eq!(request["signature"]["signer"], logistics_id.as_str());
+        assert_ne!(request["signature"]["signer"], node_id.as_str());
+        assert_eq!(unknown, 400);
+        assert_eq!(mismatch, 400);
+        assert_eq!(batch[0]["error"]["code"], 400);
+        assert_eq!(desired_state, 400);
+    });
+}
@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum KeystoreError {
+    #[error("Keystore {0} can not be read: {1}")]
+    Read(String, String),
+    #[error("Keystore {0} can not be written: {1}")]
+    Write(String, String),
+    #[error("Keystore is not valid: {0}")]
+    Invalid(String),
+    #[error("Unsupported keystore {0}")]
+    Unsupported(String),
+    #[error("Wrong passphrase or corrupted keystore")]
+    Decryption,
+    #[error("Passphrase can not be read: {0}")]
+    Passphrase(String),
+    #[error("Passphrases do not match")]
+    PassphraseMismatch,
+}
@@ -0,0 +1,24 @@
+use libp2p::identity::{ed25519::Keypair as EdKeyPair, secp256k1::SecretKey, Keypair};
+use libp2p::PeerId;
+use taple_core::crypto::{KeyMaterial, KeyPair};
+use taple_core::identifier::{Derivable, KeyIdentifier};
+
+/// Identifier of the node in TAPLE, derived from its public key
+pub fn controller_id(keys: &KeyPair) -> String {
+    KeyIdentifier::new(keys.get_key_derivator(), &keys.public_key_bytes()).to_str()
+}
+
+/// Identifier of the node in the P2P network, derived from its key pair
+pub fn peer_id(keys: &KeyPair) -> String {
+    let keypair = match keys {
+        KeyPair::Ed25519(keys) => Keypair::Ed25519(
+            EdKeyPair::decode(&mut keys.to_bytes()).expect("Decode of Ed25519 possible"),
+        ),
+        KeyPair::Secp256k1(keys) => Keypair::Secp256k1(
+            SecretKey::from_bytes(&mut keys.secret_key_bytes())
+                .expect("Be a valid Secp256k1 secret key")
+                .into(),
+        ),
+    };
+    PeerId::from_public_key(&keypair.public()).to_string()
+}
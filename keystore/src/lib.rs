@@ -0,0 +1,310 @@
+//! Password protected keystore for the identity of TAPLE nodes.
+//!
+//! The secret key is encrypted with ChaCha20-Poly1305 using a key derived from the
+//! passphrase with scrypt. The file is a JSON document that also keeps, unencrypted,
+//! the algorithm and the controller id of the key pair
+mod error;
+mod identity;
+
+use std::io::Write;
+
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use serde::{Deserialize, Serialize};
+use taple_core::{
+    crypto::{KeyMaterial, KeyPair},
+    KeyDerivator,
+};
+use zeroize::Zeroizing;
+
+pub use error::KeystoreError;
+pub use identity::{controller_id, peer_id};
+
+/// Environment variable from which the passphrase is read when no file is given
+pub const PASSPHRASE_ENV: &str = "TAPLE_KEYSTORE_PASSPHRASE";
+
+const VERSION: u32 = 1;
+const SALT_SIZE: usize = 32;
+const KEY_SIZE: usize = 32;
+// Recommended scrypt parameters for interactive logins
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+// Limits of the scrypt parameters read from keystores, so a crafted file can not make the
+// derivation take unbounded memory or time. Up to 1 GiB with the maximum log_n and r
+const MAX_SCRYPT_LOG_N: u8 = 20;
+const MAX_SCRYPT_R: u32 = 8;
+const MAX_SCRYPT_P: u32 = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    /// Algorithm of the key pair: "ed25519" or "secp256k1"
+    pub key_derivator: String,
+    pub controller_id: String,
+    kdf: Kdf,
+    cipher: Cipher,
+    /// Encrypted secret key, in hexadecimal
+    ciphertext: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "lowercase")]
+enum Kdf {
+    Scrypt {
+        log_n: u8,
+        r: u32,
+        p: u32,
+        salt: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "lowercase")]
+enum Cipher {
+    Chacha20poly1305 { nonce: String },
+}
+
+impl Keystore {
+    /// Encrypts the secret key of a key pair with the passphrase
+    pub fn encrypt(keys: &KeyPair, passphrase: &str) -> Result<Self, KeystoreError> {
+        let mut salt = [0u8; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        let kdf = Kdf::Scrypt {
+            log_n: SCRYPT_LOG_N,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+            salt: hex::encode(salt),
+        };
+        let cipher = ChaCha20Poly1305::new(&kdf.derive_key(passphrase)?);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let secret_key = Zeroizing::new(keys.secret_key_bytes());
+        let ciphertext = cipher
+            .encrypt(&nonce, secret_key.as_slice())
+            .map_err(|_| KeystoreError::Invalid("The secret key can not be encrypted".into()))?;
+        let derivator = keys.get_key_derivator();
+        Ok(Self {
+            version: VERSION,
+            key_derivator: derivator_name(derivator).to_owned(),
+            controller_id: controller_id(keys),
+            kdf,
+            cipher: Cipher::Chacha20poly1305 {
+                nonce: hex::encode(nonce),
+            },
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    /// Decrypts the key pair, checking that it is the one of the controller id
+    pub fn decrypt(&self, passphrase: &str) -> Result<KeyPair, KeystoreError> {
+        if self.version != VERSION {
+            return Err(KeystoreError::Unsupported(format!(
+                "version {}",
+                self.version
+            )));
+        }
+        let derivator = match self.key_derivator.as_str() {
+            "ed25519" => KeyDerivator::Ed25519,
+            "secp256k1" => KeyDerivator::Secp256k1,
+            other => return Err(KeystoreError::Unsupported(format!("algorithm {}", other))),
+        };
+        let Cipher::Chacha20poly1305 { nonce } = &self.cipher;
+        let nonce = decode_hex("nonce", nonce)?;
+        if nonce.len() != 12 {
+            return Err(KeystoreError::Invalid("nonce".into()));
+        }
+        let ciphertext = decode_hex("ciphertext", &self.ciphertext)?;
+        let cipher = ChaCha20Poly1305::new(&self.kdf.derive_key(passphrase)?);
+        let secret_key = Zeroizing::new(
+            cipher
+                .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+                .map_err(|_| KeystoreError::Decryption)?,
+        );
+        let secret_key = Zeroizing::new(hex::encode(secret_key.as_slice()));
+        let keys = KeyPair::from_hex(&derivator, &secret_key)
+            .map_err(|_| KeystoreError::Invalid("secret key".into()))?;
+        if controller_id(&keys) != self.controller_id {
+            return Err(KeystoreError::Invalid(format!(
+                "the key pair is not the one of {}",
+                self.controller_id
+            )));
+        }
+        Ok(keys)
+    }
+
+    pub fn read(path: &str) -> Result<Self, KeystoreError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|error| KeystoreError::Read(path.to_owned(), error.to_string()))?;
+        serde_json::from_str(&content).map_err(|error| KeystoreError::Invalid(error.to_string()))
+    }
+
+    /// Writes the keystore to a new file, readable only by its owner. Existing files are not overwritten
+    pub fn write(&self, path: &str) -> Result<(), KeystoreError> {
+        let write_error =
+            |error: std::io::Error| KeystoreError::Write(path.to_owned(), error.to_string());
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(path).map_err(write_error)?;
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|error| KeystoreError::Invalid(error.to_string()))?;
+        file.write_all(content.as_bytes()).map_err(write_error)
+    }
+}
+
+impl Kdf {
+    fn derive_key(&self, passphrase: &str) -> Result<Key, KeystoreError> {
+        let Kdf::Scrypt { log_n, r, p, salt } = self;
+        if *log_n > MAX_SCRYPT_LOG_N || *r > MAX_SCRYPT_R || *p > MAX_SCRYPT_P {
+            return Err(KeystoreError::Unsupported(format!(
+                "scrypt parameters log_n {}, r {}, p {}. The maximum are {}, {} and {}",
+                log_n, r, p, MAX_SCRYPT_LOG_N, MAX_SCRYPT_R, MAX_SCRYPT_P
+            )));
+        }
+        let salt = decode_hex("salt", salt)?;
+        let params = scrypt::Params::new(*log_n, *r, *p, KEY_SIZE)
+            .map_err(|error| KeystoreError::Invalid(format!("scrypt parameters: {}", error)))?;
+        let mut key = Key::default();
+        scrypt::scrypt(passphrase.as_bytes(), &salt, &params, &mut key)
+            .map_err(|error| KeystoreError::Invalid(format!("scrypt parameters: {}", error)))?;
+        Ok(key)
+    }
+}
+
+/// Obtains the passphrase of a keystore from, in order, the given file, the
+/// [`PASSPHRASE_ENV`] environment variable or a prompt in the terminal.
+/// New passphrases are asked twice when prompted
+pub fn passphrase(file: Option<&str>, confirm: bool) -> Result<String, KeystoreError> {
+    if let Some(passphrase) = configured_passphrase(file)? {
+        return Ok(passphrase);
+    }
+    let prompt = |message: &str| {
+        rpassword::prompt_password(message)
+            .map_err(|error| KeystoreError::Passphrase(error.to_string()))
+    };
+    let passphrase = prompt("Keystore passphrase: ")?;
+    if confirm && prompt("Repeat the passphrase: ")? != passphrase {
+        return Err(KeystoreError::PassphraseMismatch);
+    }
+    Ok(passphrase)
+}
+
+/// Obtains the passphrase of a keystore from the given file or the [`PASSPHRASE_ENV`]
+/// environment variable, without prompting
+pub fn configured_passphrase(file: Option<&str>) -> Result<Option<String>, KeystoreError> {
+    if let Some(file) = file {
+        let content = std::fs::read_to_string(file)
+            .map_err(|error| KeystoreError::Passphrase(format!("{}: {}", file, error)))?;
+        return Ok(Some(content.trim_end_matches(['\r', '\n']).to_owned()));
+    }
+    Ok(std::env::var(PASSPHRASE_ENV).ok())
+}
+
+fn derivator_name(derivator: KeyDerivator) -> &'static str {
+    match derivator {
+        KeyDerivator::Ed25519 => "ed25519",
+        KeyDerivator::Secp256k1 => "secp256k1",
+    }
+}
+
+fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>, KeystoreError> {
+    hex::decode(value).map_err(|_| KeystoreError::Invalid(field.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use taple_core::crypto::{Ed25519KeyPair, KeyGenerator, Secp256k1KeyPair};
+
+    use super::*;
+
+    const PASSPHRASE: &str = "passphrase";
+
+    fn ed25519() -> KeyPair {
+        KeyPair::Ed25519(Ed25519KeyPair::from_seed(&[]))
+    }
+
+    /// Flips the first byte of a hexadecimal value
+    fn tamper(value: &str) -> String {
+        let mut bytes = hex::decode(value).unwrap();
+        bytes[0] ^= 0xff;
+        hex::encode(bytes)
+    }
+
+    #[test]
+    fn decrypts_the_encrypted_key_pair() {
+        for keys in [
+            ed25519(),
+            KeyPair::Secp256k1(Secp256k1KeyPair::from_seed(&[])),
+        ] {
+            let keystore = Keystore::encrypt(&keys, PASSPHRASE).unwrap();
+            assert_eq!(keystore.controller_id, controller_id(&keys));
+            let decrypted = keystore.decrypt(PASSPHRASE).unwrap();
+            assert_eq!(decrypted.secret_key_bytes(), keys.secret_key_bytes());
+            assert_eq!(controller_id(&decrypted), controller_id(&keys));
+        }
+    }
+
+    #[test]
+    fn rejects_a_wrong_passphrase() {
+        let keystore = Keystore::encrypt(&ed25519(), PASSPHRASE).unwrap();
+        assert!(matches!(
+            keystore.decrypt("other passphrase"),
+            Err(KeystoreError::Decryption)
+        ));
+    }
+
+    #[test]
+    fn rejects_a_tampered_ciphertext_or_nonce() {
+        let keystore = Keystore::encrypt(&ed25519(), PASSPHRASE).unwrap();
+        let mut tampered = keystore.clone();
+        tampered.ciphertext = tamper(&keystore.ciphertext);
+        assert!(matches!(
+            tampered.decrypt(PASSPHRASE),
+            Err(KeystoreError::Decryption)
+        ));
+        let mut tampered = keystore.clone();
+        let Cipher::Chacha20poly1305 { nonce } = &keystore.cipher;
+        tampered.cipher = Cipher::Chacha20poly1305 {
+            nonce: tamper(nonce),
+        };
+        assert!(matches!(
+            tampered.decrypt(PASSPHRASE),
+            Err(KeystoreError::Decryption)
+        ));
+    }
+
+    #[test]
+    fn rejects_a_key_pair_of_other_controller() {
+        let mut keystore = Keystore::encrypt(&ed25519(), PASSPHRASE).unwrap();
+        keystore.controller_id = controller_id(&ed25519());
+        assert!(matches!(
+            keystore.decrypt(PASSPHRASE),
+            Err(KeystoreError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_scrypt_parameters_above_the_limits() {
+        let keystore = Keystore::encrypt(&ed25519(), PASSPHRASE).unwrap();
+        let Kdf::Scrypt { salt, .. } = &keystore.kdf;
+        for (log_n, r, p) in [(MAX_SCRYPT_LOG_N + 1, 8, 1), (15, 1024, 1), (15, 8, 1024)] {
+            let mut tampered = keystore.clone();
+            tampered.kdf = Kdf::Scrypt {
+                log_n,
+                r,
+                p,
+                salt: salt.clone(),
+            };
+            assert!(matches!(
+                tampered.decrypt(PASSPHRASE),
+                Err(KeystoreError::Unsupported(_))
+            ));
+        }
+    }
+}
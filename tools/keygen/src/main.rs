@@ -1,10 +1,8 @@
 use std::str::FromStr;
 
 use clap::{Parser, ValueEnum};
-use libp2p::core::PeerId;
-use libp2p::identity::{ed25519::Keypair as EdKeyPair, secp256k1::SecretKey};
 use taple_core::crypto::{Ed25519KeyPair, KeyGenerator, KeyMaterial, KeyPair, Secp256k1KeyPair};
-use taple_core::identifier::{Derivable, KeyIdentifier};
+use taple_keystore::Keystore;
 
 #[derive(Parser, Default, Debug)]
 #[command(override_help = "
@@ -13,6 +11,8 @@ use taple_core::identifier::{Derivable, KeyIdentifier};
 \x1b[1m\x1b[4mOptions\x1b[0m:
     \x1b[1m-m, --mode\x1b[0m           Algorithm to use: ed25519 (default), secp256k1
     \x1b[1m-f, --format\x1b[0m         Output format: yaml(default), json
+    \x1b[1m-k, --keystore\x1b[0m       Keystore file in which to write the private key, encrypted with a passphrase
+    \x1b[1m--passphrase-file\x1b[0m    File with the passphrase of the keystore. Otherwise it is read from TAPLE_KEYSTORE_PASSPHRASE or prompted
     \x1b[1m-h, --help\x1b[0m           Print help information
     \x1b[1m-V, --version\x1b[0m        Print version information  
     ")]
@@ -23,6 +23,12 @@ struct Args {
     mode: Option<Algorithm>,
     #[clap(short = 'f', long = "format")]
     format: Option<Format>,
+    /// Keystore file in which to write the private key instead of printing it
+    #[clap(short = 'k', long = "keystore")]
+    keystore: Option<String>,
+    /// File with the passphrase of the keystore
+    #[clap(long = "passphrase-file", requires = "keystore")]
+    passphrase_file: Option<String>,
 }
 
 #[derive(Parser, Clone, Debug, ValueEnum, Default)]
@@ -54,59 +60,39 @@ impl FromStr for Format {
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Args = Args::parse();
     let format = args.format.unwrap_or(Format::Yaml);
-    let (kp, peer_id) = match args.mode.unwrap_or(Algorithm::Ed25519) {
-        Algorithm::Ed25519 => {
-            let keys = generate_ed25519();
-            let peer_id = PeerId::from_public_key(
-                &libp2p::identity::Keypair::Ed25519(
-                    EdKeyPair::decode(&mut keys.to_bytes()).expect("Decode of Ed25519 possible"),
-                )
-                .public(),
-            );
-            let keys = KeyPair::Ed25519(keys);
-            (keys, peer_id)
-        }
-        Algorithm::Secp256k1 => {
-            let keys = generate_secp256k1();
-            let peer_id = PeerId::from_public_key(
-                &libp2p::identity::Keypair::Secp256k1(
-                    SecretKey::from_bytes(&mut keys.secret_key_bytes())
-                        .expect("Be a valid Secp256k1 secret key")
-                        .into(),
-                )
-                .public(),
-            );
-            let keys = KeyPair::Secp256k1(keys);
-            (keys, peer_id)
-        }
+    let kp = match args.mode.unwrap_or(Algorithm::Ed25519) {
+        Algorithm::Ed25519 => KeyPair::Ed25519(generate_ed25519()),
+        Algorithm::Secp256k1 => KeyPair::Secp256k1(generate_secp256k1()),
     };
 
-    show_data(kp, peer_id, format);
+    let data = match args.keystore {
+        Some(path) => {
+            let passphrase = taple_keystore::passphrase(args.passphrase_file.as_deref(), true)?;
+            Keystore::encrypt(&kp, &passphrase)?.write(&path)?;
+            serde_json::json!({
+                "keystore": path,
+                "controller_id": taple_keystore::controller_id(&kp),
+                "peer_id": taple_keystore::peer_id(&kp)
+            })
+        }
+        None => serde_json::json!({
+            "private_key": hex::encode(kp.secret_key_bytes()),
+            "controller_id": taple_keystore::controller_id(&kp),
+            "peer_id": taple_keystore::peer_id(&kp)
+        }),
+    };
+    show_data(data, format);
     Ok(())
 }
 
-fn show_data(kp: KeyPair, peer_id: PeerId, format: Format) {
-    let private_key = kp.secret_key_bytes();
-    let hex_private_key = hex::encode(private_key);
-    let public_key = kp.public_key_bytes();
-    let key_identifier = KeyIdentifier::new(kp.get_key_derivator(), &public_key).to_str();
+fn show_data(data: serde_json::Value, format: Format) {
     match format {
         Format::Json => {
-            let json = serde_json::to_string_pretty(&serde_json::json!({
-                "private_key": hex_private_key,
-                "controller_id": key_identifier,
-                "peer_id": peer_id.to_string()
-            }))
-            .expect("JSON serialization possible");
+            let json = serde_json::to_string_pretty(&data).expect("JSON serialization possible");
             println!("{}", json);
         }
         Format::Yaml => {
-            let yaml = serde_yaml::to_string(&serde_json::json!({
-                "private_key": hex_private_key,
-                "controller_id": key_identifier,
-                "peer_id": peer_id.to_string()
-            }))
-            .expect("YAML serialization possible");
+            let yaml = serde_yaml::to_string(&data).expect("YAML serialization possible");
             println!("{}", yaml);
         }
     }
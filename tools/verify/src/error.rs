@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub(crate) enum TapleVerifyError {
+    #[error("File {0} can not be read: {1}")]
+    Read(String, String),
+    #[error("Input is not valid: {0}")]
+    InvalidInput(String),
+    #[error("Validator {0} is not a valid KeyIdentifier")]
+    InvalidValidator(String),
+}
@@ -0,0 +1,111 @@
+use std::error::Error;
+use std::io::Read;
+use std::str::FromStr;
+mod error;
+
+use clap::{Parser, Subcommand};
+use serde_json::Value;
+use taple_core::identifier::KeyIdentifier;
+use taple_verification::Quorum;
+
+use crate::error::TapleVerifyError;
+
+#[derive(Parser, Debug)]
+#[clap(version, about = "TAPLE subject chain and validation proof verifier")]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Verifies the chain of events of a subject
+    Chain {
+        /// JSON file with the events of the subject, as returned by the API REST. "-" reads the standard input
+        events: String,
+    },
+    /// Verifies the signatures of a validation proof and the quorum of validators
+    Proof {
+        /// JSON file with the proof and its signatures, as returned by the API REST. "-" reads the standard input
+        proof: String,
+        /// Public key of a validator of the governance
+        #[arg(long = "validator", required = true)]
+        validators: Vec<String>,
+        /// Quorum of validators: "majority", "fixed:<n>" or "percentage:<fraction>"
+        #[arg(long, default_value = "majority", value_parser = parse_quorum)]
+        quorum: Quorum,
+    },
+}
+
+/// Prints the report of the verification. Exits with 1 on errors and with 2 if the verification fails
+fn main() {
+    match run() {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(2),
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run() -> Result<bool, Box<dyn Error>> {
+    let args = Args::parse();
+    match args.command {
+        Command::Chain { events } => {
+            let events = taple_verification::parse_events(read_json(&events)?)
+                .map_err(|error| TapleVerifyError::InvalidInput(error.to_string()))?;
+            let report = taple_verification::verify(&events);
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            Ok(report.is_valid())
+        }
+        Command::Proof {
+            proof,
+            validators,
+            quorum,
+        } => {
+            let (proof, signatures) = taple_verification::parse_proof(read_json(&proof)?)
+                .map_err(|error| TapleVerifyError::InvalidInput(error.to_string()))?;
+            let validators = validators
+                .iter()
+                .map(|validator| {
+                    KeyIdentifier::from_str(validator)
+                        .map_err(|_| TapleVerifyError::InvalidValidator(validator.clone()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let report =
+                taple_verification::verify_proof(&proof, &signatures, &validators, quorum)?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            Ok(report.quorum_met)
+        }
+    }
+}
+
+fn read_json(path: &str) -> Result<Value, TapleVerifyError> {
+    let content = if path == "-" {
+        let mut content = String::new();
+        std::io::stdin()
+            .read_to_string(&mut content)
+            .map(|_| content)
+    } else {
+        std::fs::read_to_string(path)
+    }
+    .map_err(|error| TapleVerifyError::Read(path.to_owned(), error.to_string()))?;
+    serde_json::from_str(&content)
+        .map_err(|error| TapleVerifyError::InvalidInput(error.to_string()))
+}
+
+fn parse_quorum(quorum: &str) -> Result<Quorum, String> {
+    let invalid = || format!("Invalid quorum {}", quorum);
+    let quorum = match quorum.split_once(':') {
+        None if quorum == "majority" => Quorum::Majority,
+        Some(("fixed", required)) => required.parse().map(Quorum::Fixed).map_err(|_| invalid())?,
+        Some(("percentage", fraction)) => fraction
+            .parse()
+            .map(Quorum::Percentage)
+            .map_err(|_| invalid())?,
+        _ => return Err(invalid()),
+    };
+    quorum.validate().map_err(|error| error.to_string())?;
+    Ok(quorum)
+}
@@ -0,0 +1,420 @@
+use borsh::BorshSerialize;
+use serde::Serialize;
+use serde_json::{json, Value};
+use taple_core::{
+    identifier::{Derivable, DigestIdentifier, KeyIdentifier},
+    request::EventRequest,
+    signature::Signed,
+    Event, ValueWrapper,
+};
+
+/// Check that an event of the chain can fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    /// Sequence numbers start at 0 and are contiguous
+    Sequence,
+    /// Every event belongs to the same subject
+    Subject,
+    /// `hash_prev_event` is the hash of the previous event
+    PreviousEvent,
+    /// The signature is valid for the content of the event
+    Signature,
+    /// The event is signed by the key of the subject
+    Signer,
+    /// The patch of the event can be applied to the state
+    Patch,
+    /// The replayed state matches `state_hash`
+    StateHash,
+}
+
+impl Check {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Check::Sequence => "sequence",
+            Check::Subject => "subject",
+            Check::PreviousEvent => "previous_event",
+            Check::Signature => "signature",
+            Check::Signer => "signer",
+            Check::Patch => "patch",
+            Check::StateHash => "state_hash",
+        }
+    }
+}
+
+/// First event of the chain that failed a check
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenLink {
+    /// Position of the event in the chain
+    pub sn: u64,
+    pub check: Check,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub subject_id: Option<String>,
+    /// Number of events that passed every check
+    pub verified_events: u64,
+    pub total_events: u64,
+    pub first_broken: Option<BrokenLink>,
+}
+
+impl Report {
+    pub fn is_valid(&self) -> bool {
+        self.first_broken.is_none()
+    }
+}
+
+/// Walks the chain from the genesis event, stopping at the first event that fails a check
+pub fn verify(events: &[Signed<Event>]) -> Report {
+    let mut report = Report {
+        subject_id: events
+            .first()
+            .map(|event| event.content.subject_id.to_str()),
+        verified_events: 0,
+        total_events: events.len() as u64,
+        first_broken: None,
+    };
+    let mut state = json!({});
+    let mut subject_key: Option<KeyIdentifier> = None;
+    let mut previous: Option<&Signed<Event>> = None;
+    for (sn, event) in events.iter().enumerate() {
+        let sn = sn as u64;
+        let result = verify_event(sn, event, previous, &mut subject_key, &mut state);
+        if let Err((check, error)) = result {
+            report.first_broken = Some(BrokenLink { sn, check, error });
+            break;
+        }
+        report.verified_events += 1;
+        previous = Some(event);
+    }
+    report
+}
+
+fn verify_event(
+    sn: u64,
+    event: &Signed<Event>,
+    previous: Option<&Signed<Event>>,
+    subject_key: &mut Option<KeyIdentifier>,
+    state: &mut Value,
+) -> Result<(), (Check, String)> {
+    let content = &event.content;
+    if content.sn != sn {
+        return Err((
+            Check::Sequence,
+            format!("Expected event {} but found event {}", sn, content.sn),
+        ));
+    }
+    if let Some(previous) = previous {
+        if content.subject_id != previous.content.subject_id {
+            return Err((
+                Check::Subject,
+                format!("Event belongs to subject {}", content.subject_id.to_str()),
+            ));
+        }
+        let previous_hash = hash_like(&previous.content, &content.hash_prev_event)
+            .map_err(|error| (Check::PreviousEvent, error))?;
+        if previous_hash != content.hash_prev_event {
+            return Err((
+                Check::PreviousEvent,
+                format!(
+                    "hash_prev_event is {} but the previous event hashes to {}",
+                    content.hash_prev_event.to_str(),
+                    previous_hash.to_str()
+                ),
+            ));
+        }
+    }
+    event
+        .verify()
+        .map_err(|error| (Check::Signature, error.to_string()))?;
+    // The genesis event is signed with the key of the new subject, that transfers replace
+    if let (0, EventRequest::Create(request)) = (sn, &content.event_request.content) {
+        *subject_key = Some(request.public_key.clone());
+    }
+    if let Some(key) = subject_key.as_ref() {
+        if &event.signature.signer != key {
+            return Err((
+                Check::Signer,
+                format!(
+                    "Signed by {} instead of the subject key {}",
+                    event.signature.signer.to_str(),
+                    key.to_str()
+                ),
+            ));
+        }
+    }
+    if let EventRequest::Transfer(request) = &content.event_request.content {
+        if content.approved {
+            *subject_key = Some(request.public_key.clone());
+        }
+    }
+    apply_event(state, content).map_err(|error| (Check::Patch, error))?;
+    let hash = state_hash(state, &content.state_hash).map_err(|error| (Check::StateHash, error))?;
+    if hash != content.state_hash {
+        return Err((
+            Check::StateHash,
+            format!(
+                "state_hash is {} but the replayed state hashes to {}",
+                content.state_hash.to_str(),
+                hash.to_str()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Applies the patch of an event to the state of its subject
+pub fn apply_event(state: &mut Value, event: &Event) -> Result<(), String> {
+    // The patches of rejected or failed events were never applied to the subject
+    if !event.eval_success || !event.approved {
+        return Ok(());
+    }
+    let patch = match serde_json::from_value::<json_patch::Patch>(event.patch.0.clone()) {
+        Ok(patch) => patch,
+        // The genesis event may carry the initial state itself
+        Err(_) if event.sn == 0 => {
+            *state = event.patch.0.clone();
+            return Ok(());
+        }
+        Err(error) => return Err(format!("Invalid patch in event {}: {}", event.sn, error)),
+    };
+    json_patch::patch(state, &patch)
+        .map_err(|error| format!("Error applying the patch of event {}: {}", event.sn, error))
+}
+
+/// Hash of a state, computed with the same algorithm as the reference hash
+pub fn state_hash(state: &Value, reference: &DigestIdentifier) -> Result<DigestIdentifier, String> {
+    hash_like(&ValueWrapper(state.clone()), reference)
+}
+
+fn hash_like<T: BorshSerialize>(
+    value: &T,
+    reference: &DigestIdentifier,
+) -> Result<DigestIdentifier, String> {
+    DigestIdentifier::from_serializable_borsh(value, reference.derivator)
+        .map_err(|error| error.to_string())
+}
+
+/// Parses the events of a subject, either as serialized by TAPLE or as returned by
+/// `GET /api/subjects/{id}/events`, which flattens the signed content.
+/// Pages of events are also accepted
+pub fn parse_events(value: Value) -> Result<Vec<Signed<Event>>, serde_json::Error> {
+    let value = match value {
+        Value::Object(mut page) if page.contains_key("items") => {
+            page.remove("items").unwrap_or_default()
+        }
+        value => value,
+    };
+    let events: Vec<Value> = serde_json::from_value(value)?;
+    events
+        .into_iter()
+        .map(|event| {
+            let mut event = unflatten(event);
+            if let Some(request) = event
+                .get_mut("content")
+                .and_then(|content| content.get_mut("event_request"))
+            {
+                *request = unflatten(request.take());
+            }
+            serde_json::from_value(event)
+        })
+        .collect()
+}
+
+fn unflatten(mut value: Value) -> Value {
+    let Some(object) = value.as_object_mut() else {
+        return value;
+    };
+    if object.contains_key("content") {
+        return value;
+    }
+    let signature = object.remove("signature").unwrap_or_default();
+    json!({ "content": value, "signature": signature })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use taple_core::{
+        crypto::{Ed25519KeyPair, KeyGenerator, KeyMaterial, KeyPair},
+        request::{FactRequest, StartRequest},
+        signature::Signature,
+        DigestDerivator,
+    };
+
+    use super::*;
+
+    const DIGEST: &str = "J8618wGO7hH4wRuEeL0Ob5XNI9Q73BlCNlV8cWBORq78";
+
+    fn hash<T: BorshSerialize>(value: &T) -> DigestIdentifier {
+        DigestIdentifier::from_serializable_borsh(value, DigestDerivator::Blake3_256).unwrap()
+    }
+
+    fn sign<T: BorshSerialize>(content: T, keys: &KeyPair) -> Signed<T> {
+        let signature = Signature::new(&content, keys, DigestDerivator::Blake3_256).unwrap();
+        Signed { content, signature }
+    }
+
+    fn event(
+        sn: u64,
+        request: EventRequest,
+        patch: Value,
+        state: &Value,
+        hash_prev_event: DigestIdentifier,
+        keys: &KeyPair,
+    ) -> Event {
+        Event {
+            subject_id: DigestIdentifier::from_str(DIGEST).unwrap(),
+            event_request: sign(request, keys),
+            sn,
+            gov_version: 0,
+            patch: ValueWrapper(patch),
+            state_hash: hash(&ValueWrapper(state.clone())),
+            eval_success: true,
+            appr_required: false,
+            approved: true,
+            hash_prev_event,
+            evaluators: Default::default(),
+            approvers: Default::default(),
+        }
+    }
+
+    /// Genesis event with the state `{"value": 0}` followed by a Fact event that sets it to 1
+    fn chain(keys: &KeyPair) -> Vec<Signed<Event>> {
+        let genesis_state = json!({ "value": 0 });
+        let genesis = event(
+            0,
+            EventRequest::Create(StartRequest {
+                governance_id: DigestIdentifier::from_str(DIGEST).unwrap(),
+                schema_id: "Example".to_owned(),
+                namespace: String::new(),
+                name: "Example".to_owned(),
+                public_key: KeyIdentifier::new(keys.get_key_derivator(), &keys.public_key_bytes()),
+            }),
+            genesis_state.clone(),
+            &genesis_state,
+            DigestIdentifier::from_str(DIGEST).unwrap(),
+            keys,
+        );
+        let fact = event(
+            1,
+            EventRequest::Fact(FactRequest {
+                subject_id: DigestIdentifier::from_str(DIGEST).unwrap(),
+                payload: ValueWrapper(json!({})),
+            }),
+            json!([{ "op": "replace", "path": "/value", "value": 1 }]),
+            &json!({ "value": 1 }),
+            hash(&genesis),
+            keys,
+        );
+        vec![sign(genesis, keys), sign(fact, keys)]
+    }
+
+    fn keys() -> KeyPair {
+        KeyPair::Ed25519(Ed25519KeyPair::from_seed(&[]))
+    }
+
+    fn first_broken(events: &[Signed<Event>]) -> Option<(u64, Check)> {
+        verify(events)
+            .first_broken
+            .map(|broken| (broken.sn, broken.check))
+    }
+
+    #[test]
+    fn verify_accepts_a_valid_chain() {
+        let report = verify(&chain(&keys()));
+        assert!(report.is_valid());
+        assert_eq!(report.verified_events, 2);
+        assert_eq!(report.total_events, 2);
+    }
+
+    #[test]
+    fn verify_detects_a_tampered_state_hash() {
+        let keys = keys();
+        let mut events = chain(&keys);
+        let mut fact = events[1].content.clone();
+        fact.state_hash = hash(&ValueWrapper(json!({ "value": 2 })));
+        events[1] = sign(fact, &keys);
+        assert_eq!(first_broken(&events), Some((1, Check::StateHash)));
+    }
+
+    #[test]
+    fn verify_detects_a_tampered_signature() {
+        let mut events = chain(&keys());
+        // The content changes after it was signed
+        events[1].content.patch = ValueWrapper(json!([
+            { "op": "replace", "path": "/value", "value": 2 }
+        ]));
+        assert_eq!(first_broken(&events), Some((1, Check::Signature)));
+        let mut events = chain(&keys());
+        // Signed by a key other than the one of the subject
+        events[1] = sign(events[1].content.clone(), &keys());
+        assert_eq!(first_broken(&events), Some((1, Check::Signer)));
+    }
+
+    #[test]
+    fn verify_detects_a_broken_chain() {
+        let keys = keys();
+        let mut events = chain(&keys);
+        let mut fact = events[1].content.clone();
+        fact.hash_prev_event = DigestIdentifier::from_str(DIGEST).unwrap();
+        events[1] = sign(fact, &keys);
+        assert_eq!(first_broken(&events), Some((1, Check::PreviousEvent)));
+        let mut events = chain(&keys);
+        events.remove(0);
+        assert_eq!(first_broken(&events), Some((0, Check::Sequence)));
+    }
+
+    #[test]
+    fn apply_event_skips_the_patches_of_rejected_events() {
+        let events = chain(&keys());
+        let mut state = json!({});
+        apply_event(&mut state, &events[0].content).unwrap();
+        assert_eq!(state, json!({ "value": 0 }));
+        let mut rejected = events[1].content.clone();
+        rejected.approved = false;
+        apply_event(&mut state, &rejected).unwrap();
+        assert_eq!(state, json!({ "value": 0 }));
+        apply_event(&mut state, &events[1].content).unwrap();
+        assert_eq!(state, json!({ "value": 1 }));
+        let mut invalid = events[1].content.clone();
+        invalid.patch = ValueWrapper(json!({ "value": 2 }));
+        assert!(apply_event(&mut state, &invalid).is_err());
+    }
+
+    #[test]
+    fn state_hash_uses_the_algorithm_of_the_reference() {
+        let state = json!({ "value": 1 });
+        let reference = hash(&ValueWrapper(json!({})));
+        assert_eq!(
+            state_hash(&state, &reference).unwrap(),
+            hash(&ValueWrapper(state))
+        );
+    }
+
+    #[test]
+    fn parse_events_accepts_the_events_of_the_api() {
+        let events = chain(&keys());
+        let serialized = serde_json::to_value(&events).unwrap();
+        assert_eq!(parse_events(serialized.clone()).unwrap().len(), 2);
+        // The API flattens the content of the events and their requests, and pages them
+        let flattened: Vec<Value> = events
+            .iter()
+            .map(|event| {
+                let mut content = serde_json::to_value(&event.content).unwrap();
+                let request = serde_json::to_value(&event.content.event_request).unwrap();
+                let mut request_content = request["content"].clone();
+                request_content["signature"] = request["signature"].clone();
+                content["event_request"] = request_content;
+                content["signature"] = serde_json::to_value(&event.signature).unwrap();
+                content
+            })
+            .collect();
+        let parsed = parse_events(json!({ "items": flattened })).unwrap();
+        assert!(verify(&parsed).is_valid());
+        assert!(parse_events(json!([{ "sn": 0 }])).is_err());
+    }
+}
@@ -0,0 +1,7 @@
+//! Verification of the data attested by TAPLE nodes: the chain of events of a subject
+//! and the validation proofs signed by the validators
+mod chain;
+mod proof;
+
+pub use chain::{apply_event, parse_events, state_hash, verify, BrokenLink, Check, Report};
+pub use proof::{parse_proof, verify_proof, InvalidQuorum, ProofReport, Quorum, SignatureCheck};
@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use taple_core::{
+    identifier::{Derivable, KeyIdentifier},
+    signature::{Signature, Signed},
+    ValidationProof,
+};
+use thiserror::Error;
+
+/// Signatures of validators required to validate an event, as defined in the policies of a governance
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Quorum {
+    /// More than half of the validators
+    #[default]
+    Majority,
+    /// A fixed number of validators, at least one
+    Fixed(u32),
+    /// A fraction, greater than 0 and up to 1, of the validators
+    Percentage(f64),
+}
+
+/// Quorum that would be met without signatures
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("Invalid quorum: {0}")]
+pub struct InvalidQuorum(String);
+
+impl Quorum {
+    /// Checks that the quorum requires at least one signature
+    pub fn validate(&self) -> Result<(), InvalidQuorum> {
+        match self {
+            Quorum::Majority => Ok(()),
+            Quorum::Fixed(0) => Err(InvalidQuorum(
+                "a fixed quorum requires at least one validator".to_owned(),
+            )),
+            Quorum::Fixed(_) => Ok(()),
+            Quorum::Percentage(fraction) if *fraction > 0.0 && *fraction <= 1.0 => Ok(()),
+            Quorum::Percentage(fraction) => Err(InvalidQuorum(format!(
+                "the percentage {} is not greater than 0 and up to 1",
+                fraction
+            ))),
+        }
+    }
+
+    /// Number of signatures required among the given number of validators, never less than one
+    pub fn required(&self, validators: usize) -> Result<u32, InvalidQuorum> {
+        self.validate()?;
+        let validators = validators as u32;
+        let required = match self {
+            Quorum::Majority => validators / 2 + 1,
+            Quorum::Fixed(required) => *required,
+            Quorum::Percentage(fraction) => (validators as f64 * fraction).ceil() as u32,
+        };
+        Ok(required.max(1))
+    }
+}
+
+/// Result of the verification of one of the signatures of a proof
+#[derive(Debug, Clone, Serialize)]
+pub struct SignatureCheck {
+    pub signer: String,
+    /// The signature is valid for the proof
+    pub valid: bool,
+    /// The signer is one of the validators
+    pub validator: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProofReport {
+    pub signatures: Vec<SignatureCheck>,
+    /// Distinct validators with a valid signature
+    pub valid_signatures: u32,
+    pub required_signatures: u32,
+    pub quorum_met: bool,
+}
+
+/// Verifies the signatures of a validation proof and whether enough validators signed it
+pub fn verify_proof(
+    proof: &ValidationProof,
+    signatures: &[Signature],
+    validators: &[KeyIdentifier],
+    quorum: Quorum,
+) -> Result<ProofReport, InvalidQuorum> {
+    let validators: HashSet<&KeyIdentifier> = validators.iter().collect();
+    let mut signers: HashSet<KeyIdentifier> = HashSet::new();
+    let signatures: Vec<SignatureCheck> = signatures
+        .iter()
+        .map(|signature| {
+            let signed = Signed {
+                content: proof.clone(),
+                signature: signature.clone(),
+            };
+            let error = signed.verify().err().map(|error| error.to_string());
+            let validator = validators.contains(&signature.signer);
+            if error.is_none() && validator {
+                signers.insert(signature.signer.clone());
+            }
+            SignatureCheck {
+                signer: signature.signer.to_str(),
+                valid: error.is_none(),
+                validator,
+                error,
+            }
+        })
+        .collect();
+    let required_signatures = quorum.required(validators.len())?;
+    let valid_signatures = signers.len() as u32;
+    Ok(ProofReport {
+        signatures,
+        valid_signatures,
+        required_signatures,
+        quorum_met: valid_signatures >= required_signatures,
+    })
+}
+
+/// Proof and signatures as returned by `GET /api/subjects/{id}/validation`
+#[derive(Deserialize)]
+struct ProofWithSignatures {
+    proof: ValidationProof,
+    signatures: Vec<Signature>,
+}
+
+/// Parses a validation proof and the signatures of the validators
+pub fn parse_proof(value: Value) -> Result<(ValidationProof, Vec<Signature>), serde_json::Error> {
+    let ProofWithSignatures { proof, signatures } = serde_json::from_value(value)?;
+    Ok((proof, signatures))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use taple_core::{
+        crypto::{Ed25519KeyPair, KeyGenerator, KeyMaterial, KeyPair},
+        identifier::DigestIdentifier,
+        DigestDerivator,
+    };
+
+    use super::*;
+
+    const DIGEST: &str = "J8618wGO7hH4wRuEeL0Ob5XNI9Q73BlCNlV8cWBORq78";
+
+    fn proof(sn: u64) -> ValidationProof {
+        let digest = DigestIdentifier::from_str(DIGEST).unwrap();
+        ValidationProof {
+            subject_id: digest.clone(),
+            schema_id: "Example".to_owned(),
+            namespace: String::new(),
+            name: "Example".to_owned(),
+            subject_public_key: validator(&keys()),
+            governance_id: digest.clone(),
+            genesis_governance_version: 0,
+            sn,
+            prev_event_hash: digest.clone(),
+            event_hash: digest,
+            governance_version: 0,
+        }
+    }
+
+    fn keys() -> KeyPair {
+        KeyPair::Ed25519(Ed25519KeyPair::from_seed(&[]))
+    }
+
+    fn validator(keys: &KeyPair) -> KeyIdentifier {
+        KeyIdentifier::new(keys.get_key_derivator(), &keys.public_key_bytes())
+    }
+
+    fn sign(proof: &ValidationProof, keys: &KeyPair) -> Signature {
+        Signature::new(proof, keys, DigestDerivator::Blake3_256).unwrap()
+    }
+
+    #[test]
+    fn quorum_requires_at_least_one_signature() {
+        assert_eq!(Quorum::Majority.required(0), Ok(1));
+        assert_eq!(Quorum::Majority.required(4), Ok(3));
+        assert_eq!(Quorum::Fixed(2).required(4), Ok(2));
+        assert_eq!(Quorum::Percentage(0.5).required(3), Ok(2));
+        assert_eq!(Quorum::Percentage(0.5).required(0), Ok(1));
+        assert!(Quorum::Fixed(0).required(4).is_err());
+        assert!(Quorum::Percentage(0.0).required(4).is_err());
+        assert!(Quorum::Percentage(-0.5).required(4).is_err());
+        assert!(Quorum::Percentage(1.5).required(4).is_err());
+        assert!(Quorum::Percentage(f64::NAN).required(4).is_err());
+    }
+
+    #[test]
+    fn verify_proof_counts_valid_signatures_of_validators() {
+        let (first, second) = (keys(), keys());
+        let proof = proof(1);
+        let validators = [validator(&first), validator(&second)];
+        let signatures = [sign(&proof, &first), sign(&proof, &first)];
+        let report = verify_proof(&proof, &signatures, &validators, Quorum::Fixed(1)).unwrap();
+        assert!(report
+            .signatures
+            .iter()
+            .all(|check| check.valid && check.validator));
+        // Repeated signatures of the same validator count once
+        assert_eq!(report.valid_signatures, 1);
+        assert!(report.quorum_met);
+        let report = verify_proof(&proof, &signatures, &validators, Quorum::Majority).unwrap();
+        assert_eq!(report.required_signatures, 2);
+        assert!(!report.quorum_met);
+        let signatures = [sign(&proof, &first), sign(&proof, &second)];
+        let report = verify_proof(&proof, &signatures, &validators, Quorum::Majority).unwrap();
+        assert_eq!(report.valid_signatures, 2);
+        assert!(report.quorum_met);
+    }
+
+    #[test]
+    fn verify_proof_ignores_invalid_signatures_and_other_signers() {
+        let (validator_keys, other) = (keys(), keys());
+        let proof = proof(1);
+        let validators = [validator(&validator_keys)];
+        // Signature of a different proof
+        let signatures = [sign(&self::proof(2), &validator_keys), sign(&proof, &other)];
+        let report = verify_proof(&proof, &signatures, &validators, Quorum::Majority).unwrap();
+        assert!(!report.signatures[0].valid);
+        assert!(report.signatures[0].validator);
+        assert!(report.signatures[1].valid);
+        assert!(!report.signatures[1].validator);
+        assert_eq!(report.valid_signatures, 0);
+        assert!(!report.quorum_met);
+    }
+
+    #[test]
+    fn verify_proof_rejects_quorums_met_without_signatures() {
+        let proof = proof(1);
+        let validators = [validator(&keys())];
+        for quorum in [
+            Quorum::Fixed(0),
+            Quorum::Percentage(0.0),
+            Quorum::Percentage(-1.0),
+            Quorum::Percentage(2.0),
+        ] {
+            assert!(verify_proof(&proof, &[], &validators, quorum).is_err());
+        }
+    }
+}